@@ -0,0 +1,99 @@
+//! `exec`: run one ad-hoc command as a single-case suite.
+//!
+//! The command goes through the same planning, timeout, logging and summary
+//! code as a manifest case, so throwaway experiments get a case log, an
+//! artifact directory and a `last_run.json` under `logs/exec/`.
+
+use std::{
+    env,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result, bail};
+use clap::Parser;
+use serde_json::json;
+
+use crate::{
+    Manifest, RunOptions, RunStatus, ScriptEnv, Selection, SuiteSpec, clock::SystemClock,
+    command_line, default_timeout, find_on_path, run_selection,
+};
+
+#[derive(Parser, Debug)]
+#[command(
+    name = "starry-test-harness exec",
+    bin_name = "starry-test-harness exec",
+    version,
+    about = "Run a single command through the harness as a one-case suite"
+)]
+pub struct ExecCli {
+    #[arg(long, default_value = ".")]
+    workspace: PathBuf,
+    /// Timeout for the command, in seconds (scaled by --timeout-multiplier)
+    #[arg(long, value_name = "SECS", default_value_t = default_timeout())]
+    timeout: u64,
+    /// Case name used in output, logs and the summary
+    #[arg(long, value_name = "NAME", default_value = "exec")]
+    name: String,
+    #[command(flatten)]
+    run: RunOptions,
+    /// Command to run and its arguments, after `--`
+    #[arg(required = true, last = true, value_name = "COMMAND")]
+    command: Vec<String>,
+}
+
+impl ExecCli {
+    /// Path of the `--tee` transcript, which `main` sets up around the run.
+    pub fn tee(&self) -> Option<&Path> {
+        self.run.tee.as_deref()
+    }
+}
+
+pub fn run(cli: ExecCli) -> Result<RunStatus> {
+    if cli.run.retry_suite > 0 {
+        bail!(
+            "--retry-suite re-runs a whole suite; use `retries` in a suite.toml for single commands"
+        );
+    }
+    let workspace = std::fs::canonicalize(&cli.workspace)
+        .with_context(|| format!("failed to resolve workspace {}", cli.workspace.display()))?;
+    let (program, args) = cli.command.split_first().expect("clap requires a command");
+    let program = resolve_program(program)?;
+
+    let manifest: Manifest = serde_json::from_value(json!({
+        "description": command_line(Path::new(&cli.command[0]), &cli.command[1..]),
+        "default_timeout_secs": cli.timeout,
+        "cases": [{
+            "name": cli.name,
+            "path": program.display().to_string(),
+            "args": args,
+        }],
+    }))
+    .context("failed to build the exec manifest")?;
+    let suite = SuiteSpec {
+        dir_name: "exec".to_string(),
+        display_name: "Exec".to_string(),
+    };
+    let selection = Selection {
+        cases: manifest.cases.clone(),
+        manifest,
+        script_env: ScriptEnv::default(),
+        shuffle: None,
+        adhoc: true,
+    };
+    run_selection(&suite, &workspace, &cli.run, selection, &SystemClock)
+}
+
+/// Absolute path of the command: paths are taken relative to the current
+/// directory, bare names are looked up on `PATH`.
+fn resolve_program(program: &str) -> Result<PathBuf> {
+    if program.contains('/') {
+        let path =
+            std::path::absolute(program).with_context(|| format!("failed to resolve {program}"))?;
+        if !path.is_file() {
+            bail!("command {} does not exist", path.display());
+        }
+        return Ok(path);
+    }
+    find_on_path(program, &env::var_os("PATH").unwrap_or_default())
+        .with_context(|| format!("command {program} not found on PATH"))
+}
@@ -0,0 +1,120 @@
+//! `list-artifacts`: what a past run left in its artifact directory.
+//!
+//! Reads the run's `summary.json` for the artifact root and its cases, then
+//! lists every file under the root with its size and the case whose
+//! directory holds it, so a core dump or generated image is one command away.
+
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+
+use crate::{RunStatus, RunSummary, history, rel_path, sanitize_case_name};
+
+/// Owner shown for files outside every case directory.
+const RUN_OWNER: &str = "(run)";
+
+struct Artifact {
+    owner: String,
+    path: PathBuf,
+    size: u64,
+}
+
+/// Lists the artifacts of run `run_id` (or `latest`). With `absolute`, prints
+/// only absolute paths, one per line, for `scp` or `xargs`.
+pub fn list(logs_root: &Path, workspace: &Path, run_id: &str, absolute: bool) -> Result<RunStatus> {
+    let (run_id, summary_path) = history::run_summary(logs_root, run_id)?;
+    let content = fs::read_to_string(&summary_path)
+        .with_context(|| format!("failed to read {}", summary_path.display()))?;
+    let summary: RunSummary = serde_json::from_str(&content)
+        .with_context(|| format!("failed to parse {}", summary_path.display()))?;
+    let root = workspace.join(&summary.artifacts_root);
+    let owners = summary
+        .cases
+        .iter()
+        .map(|case| (sanitize_case_name(case.key()), case.name.clone()))
+        .collect::<HashMap<_, _>>();
+
+    let mut files = Vec::new();
+    if root.is_dir() {
+        collect_files(&root, &mut files)
+            .with_context(|| format!("failed to read {}", root.display()))?;
+    }
+    files.sort();
+    let artifacts = files
+        .into_iter()
+        .map(|(path, size)| {
+            let owner = path
+                .strip_prefix(&root)
+                .ok()
+                .filter(|inner| inner.components().count() > 1)
+                .and_then(|inner| inner.components().next())
+                .and_then(|dir| owners.get(dir.as_os_str().to_str()?))
+                .map_or_else(|| RUN_OWNER.to_string(), Clone::clone);
+            Artifact { owner, path, size }
+        })
+        .collect::<Vec<_>>();
+
+    if absolute {
+        for artifact in &artifacts {
+            println!("{}", artifact.path.display());
+        }
+        return Ok(RunStatus::Passed);
+    }
+    let total = artifacts.iter().map(|artifact| artifact.size).sum::<u64>();
+    println!(
+        "[artifacts] run {run_id}: {} file(s), {} in {}",
+        artifacts.len(),
+        human_size(total),
+        rel_path(&root, workspace).display()
+    );
+    let width = artifacts
+        .iter()
+        .map(|artifact| artifact.owner.len())
+        .max()
+        .unwrap_or(0);
+    for artifact in &artifacts {
+        println!(
+            "  {:<width$}  {:>9}  {}",
+            artifact.owner.bright_white(),
+            human_size(artifact.size),
+            rel_path(&artifact.path, workspace)
+                .display()
+                .to_string()
+                .dimmed()
+        );
+    }
+    Ok(RunStatus::Passed)
+}
+
+/// Every regular file below `dir` with its size; symlinks are not followed.
+fn collect_files(dir: &Path, files: &mut Vec<(PathBuf, u64)>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        if metadata.is_dir() {
+            collect_files(&entry.path(), files)?;
+        } else if metadata.is_file() {
+            files.push((entry.path(), metadata.len()));
+        }
+    }
+    Ok(())
+}
+
+fn human_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit + 1 < UNITS.len() {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{size:.1} {}", UNITS[unit])
+}
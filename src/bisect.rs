@@ -0,0 +1,243 @@
+//! `--bisect`: narrow an order-dependent failure down to the case causing it.
+//!
+//! A case that passes on its own but fails in a particular (usually shuffled)
+//! order is broken by something an earlier case left behind. Starting from
+//! the failing order, the first failed case is the victim; the cases before
+//! it are halved, and each half is run in front of the victim, keeping the
+//! original order, until a single culprit remains or neither half alone
+//! reproduces the failure. Every step is a normal run in its own run
+//! directory; the steps are recorded in `bisect.json` next to `last_run.json`.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result, bail};
+use serde::Serialize;
+
+use crate::{
+    KeepRun, RunOptions, RunStatus, SuiteSpec, TestCase,
+    clock::Clock,
+    history::{self, PreviousRun},
+    logs_root, run_selection, select_cases,
+};
+
+/// Written to the suite's log root once the bisection ends.
+pub const BISECT_FILE: &str = "bisect.json";
+
+#[derive(Debug, Serialize)]
+struct Bisection {
+    /// The full order that was bisected, by case name.
+    order: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    victim: Option<String>,
+    /// The single earlier case that makes the victim fail.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    culprit: Option<String>,
+    /// The smallest order found that still fails, ending with the victim.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    minimal_order: Vec<String>,
+    steps: Vec<BisectStep>,
+}
+
+#[derive(Debug, Serialize)]
+struct BisectStep {
+    run_id: String,
+    cases: Vec<String>,
+    /// Whether the victim (or, for the first step, any case) failed.
+    failed: bool,
+}
+
+/// What every step needs to launch a run.
+struct Runner<'a> {
+    suite: &'a SuiteSpec,
+    workspace: &'a Path,
+    options: RunOptions,
+    clock: &'a dyn Clock,
+    logs_root: PathBuf,
+}
+
+pub fn run(
+    suite: &SuiteSpec,
+    workspace: &Path,
+    options: &RunOptions,
+    clock: &dyn Clock,
+) -> Result<RunStatus> {
+    if (options.shuffle || options.shuffle_groups)
+        && options.seed.is_none()
+        && options.seed_from.is_none()
+    {
+        bail!(
+            "--bisect needs the failing order: pass the --seed (or --seed-from) the failing run used"
+        );
+    }
+    // Every step's directory shows what ran in which order; keep them all.
+    let mut options = options.clone();
+    if options.keep != KeepRun::Always {
+        println!("[bisect] keeping every step's run directory (--keep ignored)");
+        options.keep = KeepRun::Always;
+    }
+    let runner = Runner {
+        suite,
+        workspace,
+        logs_root: logs_root(&options, suite, workspace)?,
+        options,
+        clock,
+    };
+    let order = select_cases(suite, workspace, &runner.options)?.cases;
+    let mut bisection = Bisection {
+        order: order.iter().map(|case| case.name.clone()).collect(),
+        victim: None,
+        culprit: None,
+        minimal_order: Vec::new(),
+        steps: Vec::new(),
+    };
+
+    println!("[bisect] step 1: the full order ({} cases)", order.len());
+    let (status, failed) = runner.run_order(&order, &mut bisection)?;
+    let victim_index = order
+        .iter()
+        .position(|case| failed.iter().any(|key| key == case_key(case)));
+    let Some(victim_index) = victim_index else {
+        if status == RunStatus::Passed {
+            println!("[bisect] the order passed; nothing to bisect (is the failure flaky?)");
+        } else {
+            println!("[bisect] no case failed (run ended {status:?}); nothing to bisect");
+        }
+        return runner.finish(&bisection, status);
+    };
+    bisection.steps[0].failed = true;
+    let victim = &order[victim_index];
+    bisection.victim = Some(victim.name.clone());
+    if victim_index == 0 {
+        println!(
+            "[bisect] {} fails first in the order; nothing ran before it",
+            victim.name
+        );
+        bisection.minimal_order = vec![victim.name.clone()];
+        return runner.finish(&bisection, RunStatus::TestsFailed);
+    }
+
+    let mut step = 2;
+    println!("[bisect] step {step}: {} on its own", victim.name);
+    if runner.victim_fails(&[], victim, &mut bisection)? {
+        println!(
+            "[bisect] {} fails on its own; the failure does not depend on the order",
+            victim.name
+        );
+        bisection.minimal_order = vec![victim.name.clone()];
+        return runner.finish(&bisection, RunStatus::TestsFailed);
+    }
+
+    let mut candidates = order[..victim_index].to_vec();
+    while candidates.len() > 1 {
+        let (first, second) = candidates.split_at(candidates.len() / 2);
+        let mut narrowed = None;
+        for half in [first, second] {
+            step += 1;
+            println!(
+                "[bisect] step {step}: {} of {} candidate(s) before {}",
+                half.len(),
+                candidates.len(),
+                victim.name
+            );
+            if runner.victim_fails(half, victim, &mut bisection)? {
+                narrowed = Some(half.to_vec());
+                break;
+            }
+        }
+        match narrowed {
+            Some(half) => candidates = half,
+            // Only cases from both halves together break the victim.
+            None => break,
+        }
+    }
+
+    bisection.minimal_order = candidates
+        .iter()
+        .chain([victim])
+        .map(|case| case.name.clone())
+        .collect();
+    if let [culprit] = candidates.as_slice() {
+        bisection.culprit = Some(culprit.name.clone());
+        println!(
+            "[bisect] culprit pair: {} -> {} ({step} steps)",
+            culprit.name, victim.name
+        );
+    } else {
+        println!(
+            "[bisect] no single case breaks {}; smallest failing order found ({step} steps): {}",
+            victim.name,
+            bisection.minimal_order.join(" -> ")
+        );
+    }
+    runner.finish(&bisection, RunStatus::TestsFailed)
+}
+
+impl Runner<'_> {
+    /// Runs `prefix` followed by `victim` and reports whether the victim failed.
+    fn victim_fails(
+        &self,
+        prefix: &[TestCase],
+        victim: &TestCase,
+        bisection: &mut Bisection,
+    ) -> Result<bool> {
+        let cases = prefix.iter().chain([victim]).cloned().collect::<Vec<_>>();
+        let (_, failed) = self.run_order(&cases, bisection)?;
+        let victim_failed = failed.iter().any(|key| key == case_key(victim));
+        if let Some(step) = bisection.steps.last_mut() {
+            step.failed = victim_failed;
+        }
+        Ok(victim_failed)
+    }
+
+    /// Runs exactly `cases`, in order, and returns the run status and the keys
+    /// of the cases that failed.
+    fn run_order(
+        &self,
+        cases: &[TestCase],
+        bisection: &mut Bisection,
+    ) -> Result<(RunStatus, Vec<String>)> {
+        let mut selection = select_cases(self.suite, self.workspace, &self.options)?;
+        selection.cases = cases.to_vec();
+        // The order is already decided; do not shuffle it again.
+        selection.shuffle = None;
+        let status = run_selection(
+            self.suite,
+            self.workspace,
+            &self.options,
+            selection,
+            self.clock,
+        )?;
+        if status == RunStatus::Interrupted {
+            bail!("interrupted during --bisect");
+        }
+        let (run_id, summary_path) = history::run_summary(&self.logs_root, "latest")?;
+        let failed = PreviousRun::load(&summary_path)
+            .with_context(|| format!("failed to read {}", summary_path.display()))?
+            .cases
+            .into_iter()
+            .filter(|case| case.status == "failed")
+            .map(|case| case.key().to_string())
+            .collect();
+        bisection.steps.push(BisectStep {
+            run_id,
+            cases: cases.iter().map(|case| case.name.clone()).collect(),
+            failed: false,
+        });
+        Ok((status, failed))
+    }
+
+    fn finish(&self, bisection: &Bisection, status: RunStatus) -> Result<RunStatus> {
+        let path = self.logs_root.join(BISECT_FILE);
+        fs::write(&path, serde_json::to_string_pretty(bisection)?)
+            .with_context(|| format!("failed to write {}", path.display()))?;
+        println!("[bisect] steps recorded in {}", path.display());
+        Ok(status)
+    }
+}
+
+fn case_key(case: &TestCase) -> &str {
+    case.id.as_deref().unwrap_or(&case.name)
+}
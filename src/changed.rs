@@ -0,0 +1,84 @@
+//! Case selection for `--only-changed`, driven by `git diff`.
+
+use std::{path::Path, process::Command};
+
+use anyhow::{Context, Result, bail};
+use glob::Pattern;
+
+use crate::TestCase;
+
+/// Files whose modification invalidates every case in every suite.
+const HARNESS_PATHS: &[&str] = &["src/", "Cargo.toml", "Cargo.lock", "Makefile"];
+
+/// What `--only-changed` decided to run.
+pub enum ChangeSelection {
+    /// The harness, the manifest or the build script changed: run everything.
+    Everything(String),
+    /// Only these cases are affected.
+    Cases(Vec<TestCase>),
+}
+
+/// Lists files changed between `base` and `HEAD`, relative to `workspace`.
+pub fn changed_files(workspace: &Path, base: &str) -> Result<Vec<String>> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(workspace)
+        .args(["diff", "--name-only", "--relative"])
+        .arg(format!("{base}...HEAD"))
+        .output()
+        .context("failed to run git")?;
+    if !output.status.success() {
+        bail!(
+            "git diff against {base} failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+                .lines()
+                .next()
+                .unwrap_or("")
+                .trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+/// Picks the cases touched by `changed`. `suite_files` are workspace-relative
+/// paths (manifest, build script) that force a full run when modified.
+pub fn select(
+    cases: &[TestCase],
+    changed: &[String],
+    suite_files: &[String],
+) -> Result<ChangeSelection> {
+    for file in changed {
+        if HARNESS_PATHS
+            .iter()
+            .any(|prefix| file == prefix.trim_end_matches('/') || file.starts_with(prefix))
+            || suite_files.contains(file)
+        {
+            return Ok(ChangeSelection::Everything(file.clone()));
+        }
+    }
+
+    let mut selected = Vec::new();
+    for case in cases {
+        let patterns = case
+            .sources
+            .iter()
+            .map(|glob| {
+                Pattern::new(glob).with_context(|| {
+                    format!("case {} has invalid sources glob `{glob}`", case.name)
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let touched = changed.iter().any(|file| {
+            file == case.path.trim_start_matches("./") || patterns.iter().any(|p| p.matches(file))
+        });
+        if touched {
+            selected.push(case.clone());
+        }
+    }
+    Ok(ChangeSelection::Cases(selected))
+}
@@ -0,0 +1,211 @@
+//! Per-case assertions declared as `[[cases.checks]]` tables.
+
+use anyhow::{Context, Result, bail};
+use regex::Regex;
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+use crate::diff::{self, Diff};
+
+/// One assertion on a finished case. Every field that is set must hold, and
+/// every check of a case must pass for the case to pass.
+#[derive(Debug, Deserialize, Clone, JsonSchema)]
+pub struct Check {
+    /// Regex that must match somewhere in the captured stdout.
+    pub stdout_regex: Option<String>,
+    /// Regex that must match somewhere in the captured stderr.
+    pub stderr_regex: Option<String>,
+    /// Text (possibly several lines) that must appear verbatim in stdout.
+    pub stdout_contains: Option<String>,
+    /// Text (possibly several lines) that must appear verbatim in stderr.
+    pub stderr_contains: Option<String>,
+    /// Exit code the command must return.
+    pub exit_code: Option<i32>,
+}
+
+/// A regex rewrite of captured stdout before the checks see it, to scrub
+/// volatile text such as timestamps, PIDs or addresses.
+#[derive(Debug, Deserialize, Clone, JsonSchema)]
+pub struct Normalize {
+    pub pattern: String,
+    /// Replacement text; `$1` or `${name}` refer to capture groups.
+    #[serde(default)]
+    pub replacement: String,
+}
+
+pub struct CompiledCheck {
+    stdout: Option<Regex>,
+    stderr: Option<Regex>,
+    stdout_contains: Option<String>,
+    stderr_contains: Option<String>,
+    exit_code: Option<i32>,
+}
+
+/// A failed check, with a diff against the output when text was compared.
+pub struct CheckFailure {
+    pub message: String,
+    pub diff: Option<Diff>,
+}
+
+/// Compiles a case's checks, rejecting invalid regexes and empty checks.
+pub fn compile(case: &str, checks: &[Check]) -> Result<Vec<CompiledCheck>> {
+    checks
+        .iter()
+        .enumerate()
+        .map(|(idx, check)| {
+            let number = idx + 1;
+            if check.stdout_regex.is_none()
+                && check.stderr_regex.is_none()
+                && check.stdout_contains.is_none()
+                && check.stderr_contains.is_none()
+                && check.exit_code.is_none()
+            {
+                bail!(
+                    "case {case}: check #{number} sets none of stdout_regex, stderr_regex, \
+                     stdout_contains, stderr_contains, exit_code"
+                );
+            }
+            let regex = |field: &str, pattern: &Option<String>| {
+                pattern
+                    .as_deref()
+                    .map(Regex::new)
+                    .transpose()
+                    .with_context(|| format!("case {case}: check #{number} has an invalid {field}"))
+            };
+            Ok(CompiledCheck {
+                stdout: regex("stdout_regex", &check.stdout_regex)?,
+                stderr: regex("stderr_regex", &check.stderr_regex)?,
+                stdout_contains: check.stdout_contains.clone(),
+                stderr_contains: check.stderr_contains.clone(),
+                exit_code: check.exit_code,
+            })
+        })
+        .collect()
+}
+
+/// Compiles `normalize` rules; `owner` names the case or suite in errors.
+pub fn compile_normalize(owner: &str, rules: &[Normalize]) -> Result<Vec<(Regex, String)>> {
+    rules
+        .iter()
+        .map(|rule| {
+            let regex = Regex::new(&rule.pattern).with_context(|| {
+                format!("{owner}: invalid normalize pattern `{}`", rule.pattern)
+            })?;
+            Ok((regex, rule.replacement.clone()))
+        })
+        .collect()
+}
+
+/// Applies every rule in order, each to the previous rule's output.
+pub fn normalize(rules: &[(Regex, String)], text: &str) -> String {
+    rules
+        .iter()
+        .fold(text.to_string(), |text, (regex, replacement)| {
+            regex.replace_all(&text, replacement.as_str()).into_owned()
+        })
+}
+
+/// Clears the output assertions of every check, keeping `exit_code`, for
+/// cases whose output is not text. Returns how many checks lost one.
+pub fn drop_output_assertions(checks: &mut [CompiledCheck]) -> usize {
+    let mut dropped = 0;
+    for check in checks {
+        let had_output = check.stdout.is_some()
+            || check.stderr.is_some()
+            || check.stdout_contains.is_some()
+            || check.stderr_contains.is_some();
+        check.stdout = None;
+        check.stderr = None;
+        check.stdout_contains = None;
+        check.stderr_contains = None;
+        dropped += usize::from(had_output);
+    }
+    dropped
+}
+
+/// Runs every check and describes each one that failed, in order.
+pub fn evaluate(
+    checks: &[CompiledCheck],
+    stdout: &str,
+    stderr: &str,
+    exit_code: Option<i32>,
+) -> Vec<CheckFailure> {
+    let mut failures = Vec::new();
+    for (idx, check) in checks.iter().enumerate() {
+        let number = idx + 1;
+        for (stream, re, output) in [
+            ("stdout", &check.stdout, stdout),
+            ("stderr", &check.stderr, stderr),
+        ] {
+            if let Some(re) = re
+                && !re.is_match(output)
+            {
+                failures.push(CheckFailure {
+                    message: format!("check #{number}: {stream} does not match `{re}`"),
+                    diff: Some(diff::unified(
+                        &regex_as_text(re.as_str()),
+                        output,
+                        &format!("check #{number} {stream}_regex"),
+                        stream,
+                    )),
+                });
+            }
+        }
+        for (stream, text, output) in [
+            ("stdout", &check.stdout_contains, stdout),
+            ("stderr", &check.stderr_contains, stderr),
+        ] {
+            if let Some(text) = text
+                && !normalized(output).contains(&normalized(text))
+            {
+                failures.push(CheckFailure {
+                    message: format!(
+                        "check #{number}: {stream} does not contain the expected text"
+                    ),
+                    diff: Some(diff::unified(
+                        text,
+                        output,
+                        &format!("check #{number} {stream}_contains"),
+                        stream,
+                    )),
+                });
+            }
+        }
+        if let Some(expected) = check.exit_code
+            && exit_code != Some(expected)
+        {
+            let actual =
+                exit_code.map_or_else(|| "no exit code".to_string(), |code| format!("exit {code}"));
+            failures.push(CheckFailure {
+                message: format!("check #{number}: expected exit {expected}, got {actual}"),
+                diff: None,
+            });
+        }
+    }
+    failures
+}
+
+/// Line endings and trailing blanks never decide a `*_contains` check.
+fn normalized(text: &str) -> String {
+    text.lines()
+        .map(str::trim_end)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// A readable stand-in for the expected text of a regex: inline flags and
+/// line anchors are dropped and `\n` escapes become line breaks.
+fn regex_as_text(pattern: &str) -> String {
+    let pattern = pattern
+        .strip_prefix("(?")
+        .and_then(|rest| rest.split_once(')'))
+        .filter(|(flags, _)| !flags.is_empty() && flags.chars().all(|c| c.is_ascii_alphabetic()))
+        .map_or(pattern, |(_, rest)| rest);
+    pattern
+        .replace(r"\n", "\n")
+        .lines()
+        .map(|line| line.strip_prefix('^').unwrap_or(line))
+        .map(|line| line.strip_suffix('$').unwrap_or(line))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
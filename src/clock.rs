@@ -0,0 +1,32 @@
+//! Wall-clock time for run ids and summary timestamps.
+//!
+//! Runs read the time through [`Clock`] so tests can pin it and assert on
+//! run directory names and `started_at`/`finished_at`. Durations and case
+//! offsets keep using the monotonic `Instant`.
+
+use chrono::{DateTime, Local};
+
+pub trait Clock {
+    fn now(&self) -> DateTime<Local>;
+}
+
+/// The real clock.
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Local> {
+        Local::now()
+    }
+}
+
+/// Always reports the same instant. Run ids only change once a second, so a
+/// second run under the same logs root would wait forever for a fresh one.
+#[cfg(test)]
+pub struct FixedClock(pub DateTime<Local>);
+
+#[cfg(test)]
+impl Clock for FixedClock {
+    fn now(&self) -> DateTime<Local> {
+        self.0
+    }
+}
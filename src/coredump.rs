@@ -0,0 +1,130 @@
+//! Collecting core dumps left behind by crashing cases.
+//!
+//! The harness lifts `RLIMIT_CORE` for every case command. After a case
+//! fails, any core file written since it started is moved into the case
+//! artifact directory so it survives workspace cleanups and CI uploads.
+
+use std::{
+    fs, io,
+    os::unix::process::CommandExt,
+    path::{Path, PathBuf},
+    process::Command,
+    time::{Duration, SystemTime},
+};
+
+const CORE_PATTERN: &str = "/proc/sys/kernel/core_pattern";
+
+/// File timestamps come from a coarse clock (and some filesystems only keep
+/// whole seconds), so a core written right after spawn can look older than it is.
+const MTIME_SLACK: Duration = Duration::from_secs(1);
+
+/// Raises the soft core-size limit to the hard limit in the child so that
+/// crashing cases can actually dump.
+pub fn allow_core_dumps(command: &mut Command) {
+    // SAFETY: getrlimit/setrlimit are async-signal-safe and touch no memory
+    // shared with the parent.
+    unsafe {
+        command.pre_exec(|| {
+            let mut limit = libc::rlimit {
+                rlim_cur: 0,
+                rlim_max: 0,
+            };
+            if libc::getrlimit(libc::RLIMIT_CORE, &mut limit) == 0 {
+                limit.rlim_cur = limit.rlim_max;
+                libc::setrlimit(libc::RLIMIT_CORE, &limit);
+            }
+            Ok(())
+        });
+    }
+}
+
+/// Where the kernel writes core files, as a directory plus a file-name prefix.
+#[derive(Debug)]
+struct CoreLocation {
+    dir: PathBuf,
+    prefix: String,
+    /// The pattern has no `%` specifiers, so only `<prefix>` itself or
+    /// `<prefix>.<pid>` (with `core_uses_pid`) can be a core file.
+    literal: bool,
+}
+
+impl CoreLocation {
+    fn matches(&self, name: &str) -> bool {
+        let Some(rest) = name.strip_prefix(&self.prefix) else {
+            return false;
+        };
+        if !self.literal {
+            return true;
+        }
+        rest.is_empty()
+            || rest
+                .strip_prefix('.')
+                .is_some_and(|pid| !pid.is_empty() && pid.bytes().all(|b| b.is_ascii_digit()))
+    }
+}
+
+/// Interprets `core_pattern`. Returns `None` when cores are piped to a
+/// handler such as systemd-coredump, or when the pattern has no literal
+/// file-name prefix to match on.
+fn core_location(cwd: &Path) -> Option<CoreLocation> {
+    let pattern = fs::read_to_string(CORE_PATTERN).unwrap_or_else(|_| "core".into());
+    let pattern = pattern.trim();
+    if pattern.is_empty() || pattern.starts_with('|') {
+        return None;
+    }
+    let pattern = Path::new(pattern);
+    let dir = match pattern.parent() {
+        Some(parent) if parent.is_absolute() => parent.to_path_buf(),
+        Some(parent) => cwd.join(parent),
+        None => cwd.to_path_buf(),
+    };
+    let name = pattern.file_name()?.to_string_lossy();
+    let prefix = name.split('%').next().unwrap_or_default().to_string();
+    if prefix.is_empty() {
+        return None;
+    }
+    Some(CoreLocation {
+        dir,
+        prefix,
+        literal: !name.contains('%'),
+    })
+}
+
+/// Moves core files created at or after `since` from the core directory
+/// (resolved against the case's `cwd`) into `artifact_dir`.
+pub fn collect(cwd: &Path, since: SystemTime, artifact_dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let Some(location) = core_location(cwd) else {
+        return Ok(Vec::new());
+    };
+    let Ok(entries) = fs::read_dir(&location.dir) else {
+        return Ok(Vec::new());
+    };
+    let since = since.checked_sub(MTIME_SLACK).unwrap_or(since);
+    let mut collected = Vec::new();
+    for entry in entries.flatten() {
+        let name = entry.file_name();
+        if !location.matches(&name.to_string_lossy()) {
+            continue;
+        }
+        let Ok(meta) = entry.metadata() else {
+            continue;
+        };
+        if !meta.is_file() || meta.modified().map_or(true, |mtime| mtime < since) {
+            continue;
+        }
+        let dest = artifact_dir.join(&name);
+        move_file(&entry.path(), &dest)?;
+        collected.push(dest);
+    }
+    collected.sort();
+    Ok(collected)
+}
+
+/// `rename`, falling back to copy + remove across filesystems.
+fn move_file(from: &Path, to: &Path) -> io::Result<()> {
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    fs::copy(from, to)?;
+    fs::remove_file(from)
+}
@@ -0,0 +1,182 @@
+//! Line diffs for failed output checks.
+//!
+//! Expected text is compared against the stretch of actual output that
+//! resembles it most, after normalizing line endings, trailing blanks and
+//! terminal escapes, so a failure shows the few lines that actually differ.
+
+use std::sync::LazyLock;
+
+use colored::Colorize;
+use regex::Regex;
+
+/// Unchanged lines kept around each change.
+const CONTEXT: usize = 3;
+
+static ANSI_ESCAPE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\x1b\[[0-9;?]*[A-Za-z]").expect("valid escape pattern"));
+
+/// A rendered diff: header lines followed by `@@`, ` `, `-` and `+` lines.
+#[derive(Debug, Clone)]
+pub struct Diff {
+    pub lines: Vec<String>,
+}
+
+impl Diff {
+    /// Prints the diff, red for expected-only and green for actual-only lines.
+    /// `colored` already honours `NO_COLOR` / `CLICOLOR` and non-terminal output.
+    pub fn print(&self) {
+        for line in &self.lines {
+            let shown = if line.starts_with("---") || line.starts_with("+++") {
+                line.bold()
+            } else if line.starts_with("@@") {
+                line.cyan()
+            } else if line.starts_with('-') {
+                line.red()
+            } else if line.starts_with('+') {
+                line.green()
+            } else {
+                line.dimmed()
+            };
+            println!("    {shown}");
+        }
+    }
+}
+
+/// Diffs `expected` against the closest slice of `actual`. `label` names the
+/// expectation (e.g. `check #1 stdout_contains`), `stream` the actual side.
+pub fn unified(expected: &str, actual: &str, label: &str, stream: &str) -> Diff {
+    let expected = normalize(expected);
+    let actual = normalize(actual);
+    let expected = expected.iter().map(String::as_str).collect::<Vec<_>>();
+    let actual = closest_slice(&expected, &actual);
+
+    let mut lines = vec![
+        format!("--- expected: {label}"),
+        format!("+++ actual: {stream}"),
+    ];
+    let ops = diff_lines(&expected, actual);
+    for hunk in hunks(&ops) {
+        let (old_start, new_start) = position(&ops[..hunk.start]);
+        let old_len = ops[hunk.clone()]
+            .iter()
+            .filter(|op| !matches!(op, Op::Insert(_)))
+            .count();
+        let new_len = ops[hunk.clone()]
+            .iter()
+            .filter(|op| !matches!(op, Op::Delete(_)))
+            .count();
+        lines.push(format!(
+            "@@ -{},{old_len} +{},{new_len} @@",
+            old_start + 1,
+            new_start + 1
+        ));
+        for op in &ops[hunk] {
+            lines.push(match op {
+                Op::Equal(line) => format!(" {line}"),
+                Op::Delete(line) => format!("-{line}"),
+                Op::Insert(line) => format!("+{line}"),
+            });
+        }
+    }
+    Diff { lines }
+}
+
+/// Splits into lines without CR, trailing whitespace or ANSI escapes.
+fn normalize(text: &str) -> Vec<String> {
+    ANSI_ESCAPE
+        .replace_all(text, "")
+        .lines()
+        .map(|line| line.trim_end().to_string())
+        .collect()
+}
+
+/// The window of `actual` (as long as `expected`) that resembles it most,
+/// scored by shared line prefixes; the earliest one wins ties.
+fn closest_slice<'a>(expected: &[&str], actual: &'a [String]) -> &'a [String] {
+    let len = expected.len().max(1);
+    if actual.len() <= len {
+        return actual;
+    }
+    let mut best = (0, 0);
+    for start in 0..=actual.len() - len {
+        let score = actual[start..start + len]
+            .iter()
+            .zip(expected)
+            .map(|(have, want)| common_prefix(have, want))
+            .sum::<usize>();
+        if score > best.1 {
+            best = (start, score);
+        }
+    }
+    &actual[best.0..best.0 + len]
+}
+
+/// Characters two lines share before they first differ (whole equal lines
+/// get one extra point so they beat near misses).
+fn common_prefix(a: &str, b: &str) -> usize {
+    let shared = a.chars().zip(b.chars()).take_while(|(x, y)| x == y).count();
+    shared + usize::from(a == b)
+}
+
+enum Op<'a> {
+    Equal(&'a str),
+    Delete(&'a str),
+    Insert(&'a str),
+}
+
+/// Longest-common-subsequence line diff; inputs are small slices.
+fn diff_lines<'a>(old: &[&'a str], new: &'a [String]) -> Vec<Op<'a>> {
+    let (n, m) = (old.len(), new.len());
+    let mut lcs = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+    let (mut i, mut j) = (0, 0);
+    let mut ops = Vec::new();
+    while i < n || j < m {
+        if i < n && j < m && old[i] == new[j] {
+            ops.push(Op::Equal(old[i]));
+            i += 1;
+            j += 1;
+        } else if i < n && (j == m || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            ops.push(Op::Delete(old[i]));
+            i += 1;
+        } else {
+            ops.push(Op::Insert(&new[j]));
+            j += 1;
+        }
+    }
+    ops
+}
+
+/// Ranges of `ops` covering each change plus `CONTEXT` lines either side.
+fn hunks(ops: &[Op]) -> Vec<std::ops::Range<usize>> {
+    let mut ranges: Vec<std::ops::Range<usize>> = Vec::new();
+    for (idx, op) in ops.iter().enumerate() {
+        if matches!(op, Op::Equal(_)) {
+            continue;
+        }
+        let start = idx.saturating_sub(CONTEXT);
+        let end = (idx + CONTEXT + 1).min(ops.len());
+        match ranges.last_mut() {
+            Some(last) if start <= last.end => last.end = last.end.max(end),
+            _ => ranges.push(start..end),
+        }
+    }
+    ranges
+}
+
+/// Old and new line numbers (0-based) reached after `ops`.
+fn position(ops: &[Op]) -> (usize, usize) {
+    ops.iter().fold((0, 0), |(old, new), op| match op {
+        Op::Equal(_) => (old + 1, new + 1),
+        Op::Delete(_) => (old + 1, new),
+        Op::Insert(_) => (old, new + 1),
+    })
+}
@@ -0,0 +1,226 @@
+//! `--doctor`: a fast health check of every suite manifest in the workspace.
+//!
+//! Nothing is built or executed. Each `tests/<dir>/suite.toml` is parsed and
+//! checked for the mistakes that would otherwise only surface halfway through
+//! a run, and all problems are reported together.
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use colored::Colorize;
+use glob::Pattern;
+
+use crate::{
+    Manifest, RunStatus, ScriptEnv, SyscallTracer, checks, compile_classifiers, compile_sentinels,
+    load_manifest, resolve_tools,
+};
+
+/// Problems found in one suite. Warnings are reported but keep the suite healthy.
+#[derive(Default)]
+struct Report {
+    errors: Vec<String>,
+    warnings: Vec<String>,
+}
+
+/// Checks every suite under `workspace/tests` and prints a consolidated report.
+pub fn run(workspace: &Path) -> RunStatus {
+    let manifests = discover(workspace);
+    if manifests.is_empty() {
+        println!(
+            "[doctor] no suite.toml found under {}",
+            workspace.join("tests").display()
+        );
+        return RunStatus::TestsFailed;
+    }
+
+    let mut unhealthy = 0;
+    for manifest_file in &manifests {
+        let report = check_suite(workspace, manifest_file);
+        let shown = manifest_file
+            .strip_prefix(workspace)
+            .unwrap_or(manifest_file);
+        let state = if !report.errors.is_empty() {
+            unhealthy += 1;
+            "unhealthy".bright_red().bold()
+        } else if !report.warnings.is_empty() {
+            "ok (with warnings)".yellow()
+        } else {
+            "ok".bright_green()
+        };
+        println!(
+            "{} {}",
+            shown.display().to_string().bright_white().bold(),
+            state
+        );
+        for error in &report.errors {
+            println!("  {} {}", "error:".bright_red(), indent(error));
+        }
+        for warning in &report.warnings {
+            println!("  {} {}", "warning:".yellow(), indent(warning));
+        }
+    }
+
+    println!(
+        "[doctor] {} suite(s) checked, {} unhealthy",
+        manifests.len(),
+        unhealthy
+    );
+    if unhealthy == 0 {
+        RunStatus::Passed
+    } else {
+        RunStatus::TestsFailed
+    }
+}
+
+/// Keeps multi-line messages (TOML and regex errors) under their bullet.
+fn indent(message: &str) -> String {
+    message.trim_end().replace('\n', "\n    ")
+}
+
+/// Every `tests/<dir>/suite.toml`, sorted by directory.
+fn discover(workspace: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(workspace.join("tests")) else {
+        return Vec::new();
+    };
+    let mut manifests = entries
+        .flatten()
+        .map(|entry| entry.path().join("suite.toml"))
+        .filter(|path| path.is_file())
+        .collect::<Vec<_>>();
+    manifests.sort();
+    manifests
+}
+
+fn check_suite(workspace: &Path, manifest_file: &Path) -> Report {
+    let mut report = Report::default();
+    let manifest = match load_manifest(manifest_file) {
+        Ok(manifest) => manifest,
+        Err(err) => {
+            report.errors.push(format!("{err:#}"));
+            return report;
+        }
+    };
+    check_manifest(workspace, &manifest, &mut report);
+    report
+}
+
+fn check_manifest(workspace: &Path, manifest: &Manifest, report: &mut Report) {
+    if manifest.cases.is_empty() {
+        report.errors.push("no cases defined".into());
+    }
+    if let Err(err) = compile_sentinels(manifest) {
+        report.errors.push(format!("{err:#}"));
+    }
+    if let Err(err) = compile_classifiers(manifest) {
+        report.errors.push(format!("{err:#}"));
+    }
+    if let Err(err) = checks::compile_normalize("suite", &manifest.normalize) {
+        report.errors.push(format!("{err:#}"));
+    }
+    if let Some(script) = &manifest.build_script
+        && !workspace.join(script).exists()
+    {
+        report.warnings.push(format!(
+            "build_script {script} does not exist; the build step will be skipped"
+        ));
+    }
+    match ScriptEnv::resolve(manifest, workspace) {
+        Ok(script_env) => {
+            for (tool, found) in resolve_tools(manifest, &script_env, workspace) {
+                if found.is_none() {
+                    report.warnings.push(format!(
+                        "required tool {tool} is not on PATH here; runs on this machine will fail"
+                    ));
+                }
+            }
+        }
+        Err(err) => report.errors.push(format!("{err:#}")),
+    }
+    if manifest.tracer.is_some()
+        && let Ok(script_env) = ScriptEnv::resolve(manifest, workspace)
+        && let Err(reason) = SyscallTracer::resolve(manifest, &script_env, workspace)
+    {
+        report.warnings.push(format!("--trace-syscalls: {reason}"));
+    }
+    for dir in &manifest.path_prepend {
+        if !workspace.join(dir).is_dir() {
+            report.warnings.push(format!(
+                "path_prepend directory {dir} does not exist (fine if the build creates it)"
+            ));
+        }
+    }
+    if let Some(runner) = &manifest.runner
+        && runner.contains('/')
+        && !workspace.join(runner).exists()
+    {
+        report
+            .errors
+            .push(format!("runner {runner} does not exist"));
+    }
+
+    let mut names = HashMap::new();
+    let mut slugs: HashMap<String, &str> = HashMap::new();
+    for case in &manifest.cases {
+        let count = names.entry(case.name.as_str()).or_insert(0);
+        *count += 1;
+        if *count == 2 {
+            report
+                .errors
+                .push(format!("case name {} is used more than once", case.name));
+        }
+        let slug = case.slug();
+        match slugs.get(&slug) {
+            Some(other) if *other != case.name => report.errors.push(format!(
+                "cases {other} and {} share the slug {slug}; their logs would collide",
+                case.name
+            )),
+            Some(_) => {}
+            None => {
+                slugs.insert(slug, &case.name);
+            }
+        }
+
+        // Under a runner the case path names a binary inside the guest image.
+        if manifest.runner.is_none() && !workspace.join(&case.path).exists() {
+            report.errors.push(format!(
+                "case {}: script {} does not exist",
+                case.name, case.path
+            ));
+        }
+        if let Some(file) = &case.stdin_file
+            && !workspace.join(file).is_file()
+        {
+            report.errors.push(format!(
+                "case {}: stdin_file {file} does not exist",
+                case.name
+            ));
+        }
+        for (kind, script) in [
+            ("before_script", &case.before_script),
+            ("after_script", &case.after_script),
+        ] {
+            if let Some(script) = script
+                && !workspace.join(script).exists()
+            {
+                report.errors.push(format!(
+                    "case {}: {kind} {script} does not exist",
+                    case.name
+                ));
+            }
+        }
+        if let Err(err) = case.validate() {
+            report.errors.push(format!("{err:#}"));
+        }
+        for glob in &case.sources {
+            if let Err(err) = Pattern::new(glob) {
+                report.errors.push(format!(
+                    "case {}: invalid sources glob `{glob}`: {err}",
+                    case.name
+                ));
+            }
+        }
+    }
+}
@@ -0,0 +1,488 @@
+//! Spawning and supervising a single case command.
+//!
+//! The child runs as the leader of its own process group. Its pipes are
+//! drained on helper threads while the main thread polls for exit, so the
+//! harness can enforce a deadline, react to sentinel lines as they appear and
+//! clean up anything the case leaves behind.
+
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, Read},
+    os::unix::process::CommandExt,
+    path::Path,
+    process::{Command, ExitStatus, Stdio},
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+        mpsc::{self, Receiver, Sender},
+    },
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+
+use regex::Regex;
+
+use crate::{
+    interrupt,
+    procgroup::{self, ProcSnapshot},
+};
+
+/// How often the child is polled for exit unless `Supervision::poll_interval`
+/// says otherwise.
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Shortest sleep between polls; near a deadline the poller wakes up right at
+/// it instead of a full interval later, but never spins tighter than this.
+const MIN_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// Once a sentinel is seen with `stop_on_sentinel`, keep listening this long
+/// so a trailing `FAIL:` can still override an earlier `PASS:`.
+const SENTINEL_SETTLE: Duration = Duration::from_secs(1);
+
+/// How often the `--proc-snapshot` copy of a running command is refreshed.
+const SNAPSHOT_REFRESH: Duration = Duration::from_millis(200);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sentinel {
+    Pass,
+    Fail,
+}
+
+/// Output lines that decide a case outcome independently of its exit code.
+#[derive(Debug, Clone)]
+pub struct Sentinels {
+    pub pass: Regex,
+    pub fail: Regex,
+}
+
+impl Sentinels {
+    fn classify(&self, line: &str) -> Option<Sentinel> {
+        if self.fail.is_match(line) {
+            Some(Sentinel::Fail)
+        } else if self.pass.is_match(line) {
+            Some(Sentinel::Pass)
+        } else {
+            None
+        }
+    }
+}
+
+/// A sentinel match together with the line that produced it.
+#[derive(Debug, Clone)]
+pub struct SentinelHit {
+    pub kind: Sentinel,
+    pub line: String,
+}
+
+/// Where a captured line starts and when it arrived.
+#[derive(Debug, Clone, Copy)]
+pub struct LineStamp {
+    pub offset: usize,
+    pub at: SystemTime,
+}
+
+#[derive(Default)]
+pub struct Supervision<'a> {
+    /// Wall-clock limit after which the whole process group is killed.
+    pub deadline: Option<Duration>,
+    /// Soft threshold: once exceeded, `on_soft_deadline` fires a single time
+    /// and the command keeps running.
+    pub soft_deadline: Option<Duration>,
+    pub on_soft_deadline: Option<&'a dyn Fn(Duration)>,
+    /// Kill the process group once neither stdout nor stderr has produced a
+    /// byte for this long (counted from the start until the first byte).
+    pub stall_timeout: Option<Duration>,
+    /// Patterns scanned on stdout as it streams in.
+    pub sentinels: Option<&'a Sentinels>,
+    /// End the run shortly after the first sentinel instead of waiting for
+    /// the command to exit (an emulator rarely exits on its own).
+    pub stop_on_sentinel: bool,
+    /// File fed to the command's stdin; stdin is `/dev/null` otherwise.
+    pub stdin: Option<&'a Path>,
+    /// Send stderr down the stdout pipe so the captured stream keeps the
+    /// real interleaving; `Execution::stderr` is then empty.
+    pub merge_output: bool,
+    /// Time between `try_wait` polls; zero means `DEFAULT_POLL_INTERVAL`.
+    pub poll_interval: Duration,
+    /// Keep a fresh `/proc` snapshot of the command while it runs, so the
+    /// last one before it ends can be saved (`--proc-snapshot`).
+    pub proc_snapshot: bool,
+}
+
+#[derive(Debug)]
+pub struct Execution {
+    pub status: ExitStatus,
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+    /// One entry per captured line, for timestamped logs.
+    pub stdout_stamps: Vec<LineStamp>,
+    pub stderr_stamps: Vec<LineStamp>,
+    pub elapsed: Duration,
+    /// The deciding sentinel: any `FAIL:` line wins over `PASS:` lines.
+    pub sentinel: Option<SentinelHit>,
+    /// The deadline expired and the harness killed the command.
+    pub timed_out: bool,
+    /// The command went quiet for `stall_timeout` and the harness killed it.
+    pub stalled: bool,
+    /// The command outlived `soft_deadline`.
+    pub soft_deadline_exceeded: bool,
+    /// The harness was interrupted and killed the command.
+    pub interrupted: bool,
+    /// Processes that were still in the group after the command exited.
+    pub orphaned_pids: Vec<i32>,
+    /// With `proc_snapshot`: the last snapshot of the command and how long
+    /// before it ended (or was killed) it was taken.
+    pub proc_snapshot: Option<(ProcSnapshot, Duration)>,
+}
+
+/// Spawns `command` in a fresh process group and supervises it to completion.
+pub fn supervise(command: &mut Command, supervision: &Supervision) -> io::Result<Execution> {
+    let stdin = match supervision.stdin {
+        Some(path) => Stdio::from(File::open(path)?),
+        None => Stdio::null(),
+    };
+    command.process_group(0).stdin(stdin);
+    let merged = if supervision.merge_output {
+        let (reader, writer) = io::pipe()?;
+        command.stdout(writer.try_clone()?).stderr(writer);
+        Some(reader)
+    } else {
+        command.stdout(Stdio::piped()).stderr(Stdio::piped());
+        None
+    };
+
+    let start = Instant::now();
+    let mut child = command.spawn()?;
+    let pgid = child.id() as i32;
+    let stdout_pipe: Option<Box<dyn Read + Send>> = match merged {
+        Some(reader) => {
+            // Drop the parent's copies of the write end, or the reader never sees EOF.
+            command.stdout(Stdio::null()).stderr(Stdio::null());
+            Some(Box::new(reader))
+        }
+        None => child
+            .stdout
+            .take()
+            .map(|pipe| Box::new(pipe) as Box<dyn Read + Send>),
+    };
+
+    // Milliseconds after `start` at which either pipe last produced bytes.
+    let last_output = Arc::new(AtomicU64::new(0));
+    let watch = |pipe| Activity {
+        inner: pipe,
+        start,
+        last_output: Arc::clone(&last_output),
+    };
+    let stdout_pipe = stdout_pipe.map(watch);
+    let stderr_pipe = child
+        .stderr
+        .take()
+        .map(|pipe| Box::new(pipe) as Box<dyn Read + Send>)
+        .map(watch);
+
+    let (sentinel_tx, sentinel_rx) = mpsc::channel();
+    let sentinels = supervision.sentinels.cloned();
+    // Drain the pipes on helper threads: a leaked background process may keep
+    // them open long after the case itself has exited.
+    let stdout_reader =
+        thread::spawn(move || read_lines(stdout_pipe, sentinels.as_ref(), Some(sentinel_tx)));
+    let stderr_reader = thread::spawn(move || read_lines(stderr_pipe, None, None));
+
+    let mut settle_until: Option<Instant> = None;
+    let mut killed = false;
+    let mut timed_out = false;
+    let mut stalled = false;
+    let mut soft_deadline_exceeded = false;
+    let mut interrupted = false;
+    let poll_interval = if supervision.poll_interval.is_zero() {
+        DEFAULT_POLL_INTERVAL
+    } else {
+        supervision.poll_interval
+    };
+    let mut snapshot = None;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if supervision.proc_snapshot {
+            refresh_snapshot(&mut snapshot, pgid, false);
+        }
+        if supervision.stop_on_sentinel && settle_until.is_none() && sentinel_seen(&sentinel_rx) {
+            settle_until = Some(Instant::now() + SENTINEL_SETTLE);
+        }
+        let now = Instant::now();
+        if !soft_deadline_exceeded
+            && let Some(soft) = supervision.soft_deadline
+            && now.duration_since(start) >= soft
+        {
+            soft_deadline_exceeded = true;
+            if let Some(notify) = supervision.on_soft_deadline {
+                notify(soft);
+            }
+        }
+        if interrupt::requested() {
+            if supervision.proc_snapshot {
+                refresh_snapshot(&mut snapshot, pgid, true);
+            }
+            procgroup::kill_group(pgid);
+            killed = true;
+            interrupted = true;
+            break child.wait()?;
+        }
+        if settle_until.is_some_and(|until| now >= until) {
+            if supervision.proc_snapshot {
+                refresh_snapshot(&mut snapshot, pgid, true);
+            }
+            procgroup::kill_group(pgid);
+            killed = true;
+            break child.wait()?;
+        }
+        if supervision
+            .deadline
+            .is_some_and(|deadline| now.duration_since(start) >= deadline)
+        {
+            if supervision.proc_snapshot {
+                refresh_snapshot(&mut snapshot, pgid, true);
+            }
+            procgroup::kill_group(pgid);
+            killed = true;
+            timed_out = true;
+            break child.wait()?;
+        }
+        let quiet_since = start + Duration::from_millis(last_output.load(Ordering::Relaxed));
+        if supervision
+            .stall_timeout
+            .is_some_and(|stall| now.saturating_duration_since(quiet_since) >= stall)
+        {
+            if supervision.proc_snapshot {
+                refresh_snapshot(&mut snapshot, pgid, true);
+            }
+            procgroup::kill_group(pgid);
+            killed = true;
+            stalled = true;
+            break child.wait()?;
+        }
+        // Wake up at the next deadline rather than up to a full interval past it.
+        let next_deadline = [
+            supervision.deadline.map(|deadline| start + deadline),
+            supervision
+                .soft_deadline
+                .filter(|_| !soft_deadline_exceeded)
+                .map(|soft| start + soft),
+            supervision.stall_timeout.map(|stall| quiet_since + stall),
+            settle_until,
+        ]
+        .into_iter()
+        .flatten()
+        .min();
+        let sleep = match next_deadline {
+            Some(at) => poll_interval
+                .min(at.saturating_duration_since(now))
+                .max(MIN_POLL_INTERVAL),
+            None => poll_interval,
+        };
+        thread::sleep(sleep);
+    };
+    let elapsed = start.elapsed();
+    let proc_snapshot = snapshot.map(|snapshot: ProcSnapshot| {
+        let age = (start + elapsed).saturating_duration_since(snapshot.taken_at);
+        (snapshot, age)
+    });
+
+    let mut orphaned_pids = Vec::new();
+    if procgroup::group_alive(pgid) {
+        if !killed {
+            orphaned_pids = procgroup::group_members(pgid);
+        }
+        procgroup::kill_and_reap(pgid);
+    }
+
+    let stdout = stdout_reader.join().unwrap_or_default();
+    let stderr = stderr_reader.join().unwrap_or_default();
+
+    Ok(Execution {
+        status,
+        stdout: stdout.bytes,
+        stderr: stderr.bytes,
+        stdout_stamps: stdout.stamps,
+        stderr_stamps: stderr.stamps,
+        elapsed,
+        sentinel: stdout.sentinel,
+        timed_out,
+        stalled,
+        soft_deadline_exceeded,
+        interrupted,
+        orphaned_pids,
+        proc_snapshot,
+    })
+}
+
+/// Replaces `last` with a new snapshot of `pid` once it is due, or right away
+/// with `force` (the command is about to be killed). Failed reads keep the
+/// previous snapshot.
+fn refresh_snapshot(last: &mut Option<ProcSnapshot>, pid: i32, force: bool) {
+    let due = force
+        || last
+            .as_ref()
+            .is_none_or(|snapshot| snapshot.taken_at.elapsed() >= SNAPSHOT_REFRESH);
+    if due && let Some(snapshot) = procgroup::snapshot(pid) {
+        *last = Some(snapshot);
+    }
+}
+
+fn sentinel_seen(rx: &Receiver<()>) -> bool {
+    rx.try_recv().is_ok()
+}
+
+/// A pipe that records when it last returned bytes, for `stall_timeout`.
+struct Activity<R> {
+    inner: R,
+    start: Instant,
+    last_output: Arc<AtomicU64>,
+}
+
+impl<R: Read> Read for Activity<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        if read > 0 {
+            self.last_output
+                .store(self.start.elapsed().as_millis() as u64, Ordering::Relaxed);
+        }
+        Ok(read)
+    }
+}
+
+/// Everything read from one pipe.
+#[derive(Default)]
+struct Captured {
+    bytes: Vec<u8>,
+    stamps: Vec<LineStamp>,
+    /// The deciding sentinel: any `FAIL:` line wins over `PASS:` lines.
+    sentinel: Option<SentinelHit>,
+}
+
+fn read_lines<R: Read>(
+    pipe: Option<R>,
+    sentinels: Option<&Sentinels>,
+    notify: Option<Sender<()>>,
+) -> Captured {
+    let mut captured = Captured::default();
+    let Some(pipe) = pipe else {
+        return captured;
+    };
+    let mut reader = BufReader::new(pipe);
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        match reader.read_until(b'\n', &mut line) {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {}
+        }
+        captured.stamps.push(LineStamp {
+            offset: captured.bytes.len(),
+            at: SystemTime::now(),
+        });
+        captured.bytes.extend_from_slice(&line);
+        let Some(sentinels) = sentinels else {
+            continue;
+        };
+        let text = String::from_utf8_lossy(&line);
+        let text = text.trim_end_matches(['\r', '\n']);
+        if let Some(kind) = sentinels.classify(text) {
+            let overrides = match &captured.sentinel {
+                None => true,
+                Some(hit) => hit.kind == Sentinel::Pass && kind == Sentinel::Fail,
+            };
+            if overrides {
+                captured.sentinel = Some(SentinelHit {
+                    kind,
+                    line: text.to_string(),
+                });
+            }
+            if let Some(notify) = &notify {
+                let _ = notify.send(());
+            }
+        }
+    }
+    captured
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sh(script: &str) -> Command {
+        // As `run_selection` does: leftovers are re-parented to us and reaped
+        // with the group instead of lingering as zombies under init.
+        procgroup::become_subreaper();
+        let mut command = Command::new("sh");
+        command.arg("-c").arg(script);
+        command
+    }
+
+    #[test]
+    fn deadline_kills_the_command() {
+        let supervision = Supervision {
+            deadline: Some(Duration::from_millis(200)),
+            ..Supervision::default()
+        };
+        let execution = supervise(&mut sh("sleep 10"), &supervision).unwrap();
+        assert!(execution.timed_out);
+        assert!(!execution.stalled);
+        assert!(!execution.status.success());
+        assert!(
+            execution.elapsed < Duration::from_secs(5),
+            "{:?}",
+            execution.elapsed
+        );
+    }
+
+    #[test]
+    fn quiet_command_stalls() {
+        let supervision = Supervision {
+            stall_timeout: Some(Duration::from_millis(300)),
+            ..Supervision::default()
+        };
+        let execution = supervise(&mut sh("echo started; sleep 10"), &supervision).unwrap();
+        assert!(execution.stalled);
+        assert!(!execution.timed_out);
+        assert_eq!(execution.stdout, b"started\n");
+        assert!(
+            execution.elapsed < Duration::from_secs(5),
+            "{:?}",
+            execution.elapsed
+        );
+    }
+
+    #[test]
+    fn fail_line_after_pass_wins() {
+        let sentinels = Sentinels {
+            pass: Regex::new("^PASS:").unwrap(),
+            fail: Regex::new("^FAIL:").unwrap(),
+        };
+        let supervision = Supervision {
+            deadline: Some(Duration::from_secs(20)),
+            sentinels: Some(&sentinels),
+            stop_on_sentinel: true,
+            ..Supervision::default()
+        };
+        let script = "echo 'PASS: boot'; sleep 0.2; echo 'FAIL: panic'; sleep 10";
+        let execution = supervise(&mut sh(script), &supervision).unwrap();
+        let sentinel = execution.sentinel.expect("a sentinel line was printed");
+        assert_eq!(sentinel.kind, Sentinel::Fail);
+        assert_eq!(sentinel.line, "FAIL: panic");
+        // Stopped after the settle window, long before the deadline.
+        assert!(!execution.timed_out);
+    }
+
+    #[test]
+    fn backgrounded_process_is_reported_as_orphan() {
+        let execution = supervise(&mut sh("sleep 10 & echo $!"), &Supervision::default()).unwrap();
+        assert!(execution.status.success());
+        let background: i32 = String::from_utf8_lossy(&execution.stdout)
+            .trim()
+            .parse()
+            .unwrap();
+        assert_eq!(execution.orphaned_pids, [background]);
+    }
+}
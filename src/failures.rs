@@ -0,0 +1,84 @@
+//! `failures-log`: one file with the logs of every case a past run failed.
+//!
+//! Reads the run's `summary.json`, picks the failed, soft-failed and
+//! flaky-failed cases, and concatenates their logs into `failures.log` in
+//! the run directory, each under a header naming the case and its outcome.
+
+use std::{fmt::Write as _, fs, path::Path};
+
+use anyhow::{Context, Result};
+
+use crate::{CaseStatus, RunStatus, RunSummary, history, rel_path};
+
+/// Written next to the run's `summary.json`.
+pub const FAILURES_FILE: &str = "failures.log";
+
+const SEPARATOR: &str =
+    "================================================================================";
+
+/// Writes `failures.log` for run `run_id` (or `latest`) and prints where it went.
+pub fn write(logs_root: &Path, workspace: &Path, run_id: &str) -> Result<RunStatus> {
+    let (run_id, summary_path) = history::run_summary(logs_root, run_id)?;
+    let content = fs::read_to_string(&summary_path)
+        .with_context(|| format!("failed to read {}", summary_path.display()))?;
+    let summary: RunSummary = serde_json::from_str(&content)
+        .with_context(|| format!("failed to parse {}", summary_path.display()))?;
+    let failing = [
+        CaseStatus::Failed,
+        CaseStatus::SoftFailed,
+        CaseStatus::FlakyFailed,
+    ]
+    .map(|status| status.as_str());
+    let failed = summary
+        .cases
+        .iter()
+        .filter(|case| failing.contains(&case.status.as_str()))
+        .collect::<Vec<_>>();
+    if failed.is_empty() {
+        println!("[failures-log] run {run_id} has no failed cases; nothing written");
+        return Ok(RunStatus::Passed);
+    }
+
+    let mut out = String::new();
+    for case in &failed {
+        let mut outcome = case.status.clone();
+        if let Some(code) = case.exit_code {
+            write!(outcome, ", exit {code}")?;
+        }
+        write!(outcome, ", {:.2}s", case.duration_ms as f64 / 1000.0)?;
+        if case.attempts > 1 {
+            write!(outcome, ", {} attempts", case.attempts)?;
+        }
+        writeln!(out, "{SEPARATOR}")?;
+        writeln!(out, "== {} ({outcome})", case.name)?;
+        writeln!(out, "== log: {}", case.log_path.display())?;
+        for earlier in &case.attempt_logs {
+            writeln!(out, "== earlier attempt: {}", earlier.display())?;
+        }
+        if let Some(class) = &case.failure_class {
+            writeln!(out, "== failure class: {class}")?;
+        }
+        writeln!(out, "{SEPARATOR}")?;
+        let log_path = workspace.join(&case.log_path);
+        match fs::read(&log_path) {
+            Ok(log) => {
+                let log = String::from_utf8_lossy(&log);
+                out.push_str(&log);
+                if !log.ends_with('\n') {
+                    out.push('\n');
+                }
+            }
+            Err(err) => writeln!(out, "(log unavailable: {err})")?,
+        }
+        out.push('\n');
+    }
+
+    let path = summary_path.with_file_name(FAILURES_FILE);
+    fs::write(&path, out).with_context(|| format!("failed to write {}", path.display()))?;
+    println!(
+        "[failures-log] {} failed case(s) of run {run_id} -> {}",
+        failed.len(),
+        rel_path(&path, workspace).display()
+    );
+    Ok(RunStatus::Passed)
+}
@@ -0,0 +1,144 @@
+//! `--glyphs` / `--ascii`: the characters the console output is drawn with.
+//!
+//! Status marks and case boxes default to Unicode, which some terminals and
+//! CI log collectors turn into mojibake. `ascii` swaps them for `[PASS]`-style
+//! tags and `+-`/`|` boxes; `none` keeps only the words.
+
+use clap::ValueEnum;
+
+use crate::CaseStatus;
+
+/// Width of the rules around the suite header and the summary.
+const RULE_WIDTH: usize = 69;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum Glyphs {
+    /// ✓/✗/⚠ marks and box-drawing characters
+    #[default]
+    Unicode,
+    /// [PASS]/[FAIL]/[SOFT] tags and ASCII boxes
+    Ascii,
+    /// Plain words, no marks or boxes
+    None,
+}
+
+impl Glyphs {
+    /// Status shown when a case finishes, e.g. `✓ PASSED` or `[PASS]`.
+    pub fn status(self, status: &CaseStatus) -> &'static str {
+        match (self, status) {
+            (Glyphs::Unicode, CaseStatus::Passed) => "✓ PASSED",
+            (Glyphs::Unicode, CaseStatus::Failed) => "✗ FAILED",
+            (Glyphs::Unicode, CaseStatus::SoftFailed) => "⚠ SOFT FAIL",
+            (Glyphs::Unicode, CaseStatus::FlakyFailed) => "≈ FLAKY FAIL",
+            (Glyphs::Unicode, CaseStatus::Interrupted) => "■ INTERRUPTED",
+            (Glyphs::Unicode, CaseStatus::Skipped) => "⊘ SKIPPED",
+            (Glyphs::Ascii, status) => self.mark(status),
+            (Glyphs::None, CaseStatus::Passed) => "PASSED",
+            (Glyphs::None, CaseStatus::Failed) => "FAILED",
+            (Glyphs::None, CaseStatus::SoftFailed) => "SOFT FAIL",
+            (Glyphs::None, CaseStatus::FlakyFailed) => "FLAKY FAIL",
+            (Glyphs::None, CaseStatus::Interrupted) => "INTERRUPTED",
+            (Glyphs::None, CaseStatus::Skipped) => "SKIPPED",
+        }
+    }
+
+    /// Short mark in front of a `--compact` line, e.g. `✓` or `[PASS]`.
+    pub fn mark(self, status: &CaseStatus) -> &'static str {
+        match (self, status) {
+            (Glyphs::Unicode, CaseStatus::Passed) => "✓",
+            (Glyphs::Unicode, CaseStatus::Failed) => "✗",
+            (Glyphs::Unicode, CaseStatus::SoftFailed) => "⚠",
+            (Glyphs::Unicode, CaseStatus::FlakyFailed) => "≈",
+            (Glyphs::Unicode, CaseStatus::Interrupted) => "■",
+            (Glyphs::Unicode, CaseStatus::Skipped) => "⊘",
+            (Glyphs::Ascii, CaseStatus::Passed) => "[PASS]",
+            (Glyphs::Ascii, CaseStatus::Failed) => "[FAIL]",
+            (Glyphs::Ascii, CaseStatus::SoftFailed) => "[SOFT]",
+            (Glyphs::Ascii, CaseStatus::FlakyFailed) => "[FLAKY]",
+            (Glyphs::Ascii, CaseStatus::Interrupted) => "[INTR]",
+            (Glyphs::Ascii, CaseStatus::Skipped) => "[SKIP]",
+            (Glyphs::None, status) => self.status(status),
+        }
+    }
+
+    /// Mark for a found (`true`) or missing required tool in `--preflight`.
+    pub fn found(self, found: bool) -> &'static str {
+        match (self, found) {
+            (Glyphs::Unicode, true) => "✓",
+            (Glyphs::Unicode, false) => "✗",
+            (Glyphs::Ascii, true) => "[ OK ]",
+            (Glyphs::Ascii, false) => "[MISS]",
+            (Glyphs::None, true) => "found",
+            (Glyphs::None, false) => "missing",
+        }
+    }
+
+    /// First line of a case box, in front of `Test Case [i/n]`.
+    pub fn box_top(self) -> &'static str {
+        match self {
+            Glyphs::Unicode => "┌─ ",
+            Glyphs::Ascii => "+- ",
+            Glyphs::None => "",
+        }
+    }
+
+    /// Left edge of the lines inside a case box.
+    pub fn box_side(self) -> &'static str {
+        match self {
+            Glyphs::Unicode => "│ ",
+            Glyphs::Ascii => "| ",
+            Glyphs::None => "  ",
+        }
+    }
+
+    /// Left edge of the last line of a case box.
+    pub fn box_bottom(self) -> &'static str {
+        match self {
+            Glyphs::Unicode => "└─",
+            Glyphs::Ascii => "+-",
+            Glyphs::None => "  ",
+        }
+    }
+
+    /// Rule above and below the suite header and the summary.
+    pub fn rule(self) -> String {
+        match self {
+            Glyphs::Unicode => "━".repeat(RULE_WIDTH),
+            Glyphs::Ascii => "=".repeat(RULE_WIDTH),
+            Glyphs::None => String::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ascii_and_none_stay_ascii() {
+        let statuses = [
+            CaseStatus::Passed,
+            CaseStatus::Failed,
+            CaseStatus::SoftFailed,
+            CaseStatus::FlakyFailed,
+            CaseStatus::Interrupted,
+            CaseStatus::Skipped,
+        ];
+        for glyphs in [Glyphs::Ascii, Glyphs::None] {
+            let mut drawn = vec![glyphs.box_top(), glyphs.box_side(), glyphs.box_bottom()]
+                .into_iter()
+                .map(str::to_string)
+                .collect::<Vec<_>>();
+            drawn.push(glyphs.rule());
+            drawn.extend([true, false].map(|found| glyphs.found(found).to_string()));
+            for status in &statuses {
+                drawn.push(glyphs.status(status).to_string());
+                drawn.push(glyphs.mark(status).to_string());
+            }
+            for text in drawn {
+                assert!(text.is_ascii(), "{glyphs:?} draws {text:?}");
+            }
+        }
+        assert_eq!(Glyphs::Ascii.status(&CaseStatus::SoftFailed), "[SOFT]");
+    }
+}
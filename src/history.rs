@@ -0,0 +1,272 @@
+//! Reading earlier run summaries and comparing the current run against them.
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result, bail};
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+
+use crate::CaseDetail;
+
+/// Each run directory keeps its own copy of the summary under this name.
+pub const RUN_SUMMARY_FILE: &str = "summary.json";
+
+/// Run directories are named after their start time in this format.
+const RUN_ID_FORMAT: &str = "%Y%m%d-%H%M%S";
+
+/// The subset of a previous `last_run.json` that comparisons need. Unknown
+/// fields are ignored so older and newer summaries both load.
+#[derive(Debug, Deserialize)]
+pub struct PreviousRun {
+    #[serde(default)]
+    pub cases: Vec<PreviousCase>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PreviousCase {
+    pub name: String,
+    #[serde(default)]
+    pub id: Option<String>,
+    pub duration_ms: u128,
+    #[serde(default)]
+    pub status: String,
+}
+
+impl PreviousCase {
+    /// See `CaseDetail::key`.
+    pub fn key(&self) -> &str {
+        self.id.as_deref().unwrap_or(&self.name)
+    }
+}
+
+impl PreviousRun {
+    /// Loads a summary, returning `None` when it is missing or unreadable.
+    pub fn load(path: &Path) -> Option<Self> {
+        let content = fs::read_to_string(path).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    fn durations(&self) -> HashMap<&str, u128> {
+        self.cases
+            .iter()
+            .map(|case| (case.key(), case.duration_ms))
+            .collect()
+    }
+}
+
+/// A case's duration, optionally set against the previous run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaseTiming {
+    pub name: String,
+    pub duration_ms: u128,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub previous_duration_ms: Option<u128>,
+    /// Percentage change versus the previous run (positive means slower).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub change_pct: Option<f64>,
+}
+
+/// Timings for every case, ordered slowest first.
+pub fn timings(cases: &[CaseDetail], previous: Option<&PreviousRun>) -> Vec<CaseTiming> {
+    let previous = previous.map(PreviousRun::durations).unwrap_or_default();
+    let mut timings = cases
+        .iter()
+        .map(|case| {
+            let previous_duration_ms = previous.get(case.key()).copied();
+            let change_pct = previous_duration_ms
+                .filter(|prev| *prev > 0)
+                .map(|prev| (case.duration_ms as f64 - prev as f64) / prev as f64 * 100.0);
+            CaseTiming {
+                name: case.name.clone(),
+                duration_ms: case.duration_ms,
+                previous_duration_ms,
+                change_pct,
+            }
+        })
+        .collect::<Vec<_>>();
+    timings.sort_by(|a, b| b.duration_ms.cmp(&a.duration_ms));
+    timings
+}
+
+/// Which earlier run `--since` compares against.
+#[derive(Debug, Clone, Copy)]
+pub enum Since {
+    /// The Nth most recent earlier run (`1` is the previous one).
+    RunsAgo(usize),
+    /// The first run that started at or after this time.
+    At(NaiveDateTime),
+}
+
+/// Parses `--since`: a small number of runs ago, or a run id / date such as
+/// `20250107-093000`, `20250107`, `2025-01-07` or `2025-01-07T09:30:00`.
+pub fn parse_since(value: &str) -> Result<Since, String> {
+    if !value.is_empty() && value.len() <= 4 && value.bytes().all(|b| b.is_ascii_digit()) {
+        return match value.parse::<usize>() {
+            Ok(0) | Err(_) => Err("runs ago must be at least 1".into()),
+            Ok(runs) => Ok(Since::RunsAgo(runs)),
+        };
+    }
+    for format in [RUN_ID_FORMAT, "%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M:%S"] {
+        if let Ok(at) = NaiveDateTime::parse_from_str(value, format) {
+            return Ok(Since::At(at));
+        }
+    }
+    for format in ["%Y%m%d", "%Y-%m-%d"] {
+        if let Ok(date) = NaiveDate::parse_from_str(value, format) {
+            return Ok(Since::At(date.and_hms_opt(0, 0, 0).unwrap_or_default()));
+        }
+    }
+    Err(format!(
+        "expected a number of runs ago or a timestamp like 20250107-093000 or 2025-01-07, got `{value}`"
+    ))
+}
+
+/// Earlier runs under `logs_root` that kept a summary, oldest first.
+fn indexed_runs(logs_root: &Path) -> Vec<(NaiveDateTime, String, PathBuf)> {
+    let Ok(entries) = fs::read_dir(logs_root) else {
+        return Vec::new();
+    };
+    let mut runs = entries
+        .flatten()
+        .filter_map(|entry| {
+            let id = entry.file_name().to_string_lossy().into_owned();
+            let started = NaiveDateTime::parse_from_str(&id, RUN_ID_FORMAT).ok()?;
+            let summary = entry.path().join(RUN_SUMMARY_FILE);
+            summary.is_file().then_some((started, id, summary))
+        })
+        .collect::<Vec<_>>();
+    runs.sort();
+    runs
+}
+
+/// The `summary.json` of run `id` under `logs_root`, or of the newest run
+/// for `latest`. Returns the run id with the path.
+pub fn run_summary(logs_root: &Path, id: &str) -> Result<(String, PathBuf)> {
+    if id == "latest" {
+        let Some((_, id, summary)) = indexed_runs(logs_root).pop() else {
+            bail!(
+                "no run with a {RUN_SUMMARY_FILE} in {}",
+                logs_root.display()
+            );
+        };
+        return Ok((id, summary));
+    }
+    let summary = logs_root.join(id).join(RUN_SUMMARY_FILE);
+    if !summary.is_file() {
+        bail!(
+            "run {id} has no {RUN_SUMMARY_FILE} in {} (removed by --keep, or not a run id?)",
+            logs_root.display()
+        );
+    }
+    Ok((id.to_string(), summary))
+}
+
+/// The last `count` runs under `logs_root` whose summary still loads, oldest
+/// first, with their run ids.
+pub fn recent_runs(logs_root: &Path, count: usize) -> Vec<(String, PreviousRun)> {
+    let runs = indexed_runs(logs_root);
+    let skip = runs.len().saturating_sub(count);
+    runs.into_iter()
+        .skip(skip)
+        .filter_map(|(_, id, summary)| Some((id, PreviousRun::load(&summary)?)))
+        .collect()
+}
+
+/// Loads the summary of the run `since` points at, returning its run id.
+pub fn select_baseline(logs_root: &Path, since: Since) -> Result<(String, PreviousRun)> {
+    let runs = indexed_runs(logs_root);
+    let chosen = match since {
+        Since::RunsAgo(runs_ago) => runs
+            .len()
+            .checked_sub(runs_ago)
+            .and_then(|idx| runs.get(idx)),
+        Since::At(at) => runs.iter().find(|(started, _, _)| *started >= at),
+    };
+    let Some((_, id, summary)) = chosen else {
+        bail!(
+            "no earlier run with a {RUN_SUMMARY_FILE} matches --since in {} ({} indexed)",
+            logs_root.display(),
+            runs.len()
+        );
+    };
+    let content = fs::read_to_string(summary)
+        .with_context(|| format!("failed to read {}", summary.display()))?;
+    let run = serde_json::from_str(&content)
+        .with_context(|| format!("failed to parse {}", summary.display()))?;
+    Ok((id.clone(), run))
+}
+
+/// A case whose outcome differs from the baseline run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatusChange {
+    pub name: String,
+    pub before: String,
+    pub after: String,
+}
+
+/// How the current run differs from a baseline run (`--compare`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Comparison {
+    /// Run id of the baseline, or `last_run.json` for the previous run.
+    pub baseline: String,
+    /// Cases that got worse (e.g. passed -> failed).
+    pub regressed: Vec<StatusChange>,
+    /// Cases that got better (e.g. failed -> passed).
+    pub fixed: Vec<StatusChange>,
+    /// Cases the baseline did not run.
+    pub added: Vec<String>,
+    /// Baseline cases missing from this run.
+    pub removed: Vec<String>,
+}
+
+/// Orders statuses from best to worst; skipped/interrupted cases say nothing.
+fn severity(status: &str) -> Option<u8> {
+    match status {
+        "passed" => Some(0),
+        "soft_failed" | "flaky_failed" => Some(1),
+        "failed" => Some(2),
+        _ => None,
+    }
+}
+
+pub fn compare(cases: &[CaseDetail], baseline: &PreviousRun, baseline_id: &str) -> Comparison {
+    let before = baseline
+        .cases
+        .iter()
+        .map(|case| (case.key(), case.status.as_str()))
+        .collect::<HashMap<_, _>>();
+    let mut comparison = Comparison {
+        baseline: baseline_id.to_string(),
+        regressed: Vec::new(),
+        fixed: Vec::new(),
+        added: Vec::new(),
+        removed: Vec::new(),
+    };
+    for case in cases {
+        let Some(previous) = before.get(case.key()) else {
+            comparison.added.push(case.name.clone());
+            continue;
+        };
+        let change = StatusChange {
+            name: case.name.clone(),
+            before: previous.to_string(),
+            after: case.status.clone(),
+        };
+        match (severity(previous), severity(&case.status)) {
+            (Some(old), Some(new)) if new > old => comparison.regressed.push(change),
+            (Some(old), Some(new)) if new < old => comparison.fixed.push(change),
+            _ => {}
+        }
+    }
+    comparison.removed = baseline
+        .cases
+        .iter()
+        .filter(|previous| !cases.iter().any(|case| case.key() == previous.key()))
+        .map(|previous| previous.name.clone())
+        .collect();
+    comparison
+}
@@ -0,0 +1,57 @@
+//! Ctrl-C / SIGTERM handling.
+//!
+//! The first signal only raises a flag: the supervisor kills the in-flight
+//! case and the suite loop stops early so a partial summary still gets
+//! written. A second signal exits immediately.
+
+use std::{
+    sync::{
+        Once,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+static REQUESTED: AtomicBool = AtomicBool::new(false);
+static INSTALL: Once = Once::new();
+
+/// How often an interruptible sleep checks for a pending interrupt.
+const SLEEP_SLICE: Duration = Duration::from_millis(50);
+
+/// Exit code used when the run is cut short (128 + SIGINT).
+pub const EXIT_CODE: u8 = 130;
+
+/// Installs the handler; later calls (one per `--watch` iteration) are no-ops.
+pub fn install() {
+    INSTALL.call_once(|| {
+        let result = ctrlc::set_handler(|| {
+            if REQUESTED.swap(true, Ordering::SeqCst) {
+                std::process::exit(i32::from(EXIT_CODE));
+            }
+            eprintln!("\n[suite] interrupt received, stopping after cleaning up the current case (again to force)");
+        });
+        if let Err(err) = result {
+            eprintln!("[suite] warning: could not install interrupt handler: {err}");
+        }
+    });
+}
+
+/// Whether an interrupt has been received.
+pub fn requested() -> bool {
+    REQUESTED.load(Ordering::SeqCst)
+}
+
+/// Sleeps for `duration`, waking early on an interrupt. Returns `false` if
+/// the sleep was cut short.
+pub fn sleep(duration: Duration) -> bool {
+    let until = Instant::now() + duration;
+    while !requested() {
+        let now = Instant::now();
+        if now >= until {
+            return true;
+        }
+        thread::sleep((until - now).min(SLEEP_SLICE));
+    }
+    false
+}
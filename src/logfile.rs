@@ -0,0 +1,116 @@
+//! Log files with optional per-line timestamps (`--timestamps`).
+
+use std::{
+    borrow::Cow,
+    fs::File,
+    io::{self, Write},
+    path::Path,
+    time::SystemTime,
+};
+
+use chrono::{DateTime, Local};
+
+use crate::exec::LineStamp;
+
+/// A log file that, when `timestamps` is on, prefixes every line with the
+/// ISO-8601 wall-clock time it was written (or, for captured case output,
+/// the time the line arrived from the child).
+pub struct LogFile {
+    file: File,
+    timestamps: bool,
+    at_line_start: bool,
+}
+
+impl LogFile {
+    pub fn create(path: &Path, timestamps: bool) -> io::Result<Self> {
+        Ok(LogFile {
+            file: File::create(path)?,
+            timestamps,
+            at_line_start: true,
+        })
+    }
+
+    /// A second handle on the same file; both append at the shared offset.
+    pub fn try_clone(&self) -> io::Result<Self> {
+        Ok(LogFile {
+            file: self.file.try_clone()?,
+            timestamps: self.timestamps,
+            at_line_start: true,
+        })
+    }
+
+    /// Writes captured output, stamping each line with its arrival time.
+    pub fn write_captured(&mut self, bytes: &[u8], stamps: &[LineStamp]) -> io::Result<()> {
+        self.write_stamped(bytes, stamps, |chunk| Cow::Borrowed(chunk))
+    }
+
+    /// Like `write_captured`, with invalid UTF-8 replaced by U+FFFD.
+    pub fn write_captured_lossy(&mut self, bytes: &[u8], stamps: &[LineStamp]) -> io::Result<()> {
+        self.write_stamped(bytes, stamps, |chunk| {
+            match String::from_utf8_lossy(chunk) {
+                Cow::Borrowed(text) => Cow::Borrowed(text.as_bytes()),
+                Cow::Owned(text) => Cow::Owned(text.into_bytes()),
+            }
+        })
+    }
+
+    /// Writes bytes exactly as given, without timestamps, then ends the line
+    /// so the next entry starts on its own.
+    pub fn write_raw(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.file.write_all(bytes)?;
+        if !bytes.is_empty() && !bytes.ends_with(b"\n") {
+            self.file.write_all(b"\n")?;
+        }
+        self.at_line_start = true;
+        Ok(())
+    }
+
+    fn write_stamped(
+        &mut self,
+        bytes: &[u8],
+        stamps: &[LineStamp],
+        convert: impl Fn(&[u8]) -> Cow<'_, [u8]>,
+    ) -> io::Result<()> {
+        if !self.timestamps || stamps.is_empty() {
+            return self.write_all(&convert(bytes));
+        }
+        // Lines end at `\n`, which never splits a UTF-8 sequence.
+        for (idx, stamp) in stamps.iter().enumerate() {
+            let end = stamps.get(idx + 1).map_or(bytes.len(), |next| next.offset);
+            self.write_line_at(stamp.at, &convert(&bytes[stamp.offset..end]))?;
+        }
+        // Keep the next entry on its own stamped line.
+        if !self.at_line_start {
+            self.file.write_all(b"\n")?;
+            self.at_line_start = true;
+        }
+        Ok(())
+    }
+
+    fn write_line_at(&mut self, at: SystemTime, chunk: &[u8]) -> io::Result<()> {
+        if self.at_line_start {
+            let at: DateTime<Local> = at.into();
+            write!(self.file, "{} ", at.format("%Y-%m-%dT%H:%M:%S%.3f%:z"))?;
+        }
+        self.file.write_all(chunk)?;
+        self.at_line_start = chunk.ends_with(b"\n");
+        Ok(())
+    }
+}
+
+impl Write for LogFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if !self.timestamps {
+            return self.file.write(buf);
+        }
+        let now = SystemTime::now();
+        for chunk in buf.split_inclusive(|byte| *byte == b'\n') {
+            self.write_line_at(now, chunk)?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
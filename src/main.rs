@@ -1,27 +1,136 @@
+mod adhoc;
+mod artifacts;
+mod bisect;
+mod changed;
+mod checks;
+mod clock;
+mod coredump;
+mod diff;
+mod doctor;
+mod exec;
+mod failures;
+mod glyphs;
+mod history;
+mod interrupt;
+mod logfile;
+mod notice;
+mod procgroup;
+mod results;
+mod retry;
+mod serve;
+mod shuffle;
+mod tee;
+mod timeouts;
+mod trace;
+mod watch;
+
 use std::{
-    collections::{HashMap, HashSet},
+    cell::{Cell, RefCell},
+    collections::{BTreeMap, HashMap, HashSet},
     env,
-    fs::{self, File},
-    io::{IsTerminal, Write},
+    ffi::OsString,
+    fs,
+    io::{self, Write},
+    os::unix::process::ExitStatusExt,
     path::{Path, PathBuf},
-    process::Command,
-    time::Instant,
+    process::{Command, ExitCode},
+    thread,
+    time::{Duration, Instant, SystemTime},
 };
 
 use anyhow::{bail, Context, Result};
 use chrono::{DateTime, Local};
-use clap::{Parser, ValueEnum};
+use clap::{Args, Parser, ValueEnum};
 use colored::Colorize;
 use regex::Regex;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-fn main() -> Result<()> {
-    let cli = Cli::parse();
+use clock::Clock;
+use glyphs::Glyphs;
+
+fn main() -> ExitCode {
+    let mut args = env::args_os().collect::<Vec<_>>();
+    let status = if args.get(1).is_some_and(|arg| arg == "exec") {
+        args.remove(1);
+        let cli = adhoc::ExecCli::parse_from(args);
+        teed(cli.tee().map(Path::to_path_buf), || adhoc::run(cli))
+    } else {
+        let cli = Cli::parse_from(args);
+        teed(cli.run.tee.clone(), || run(cli))
+    };
+    status.into()
+}
+
+/// Runs `action` with the console copied to the `--tee` transcript, if any,
+/// so the final error message lands in it too.
+fn teed(transcript: Option<PathBuf>, action: impl FnOnce() -> Result<RunStatus>) -> RunStatus {
+    let tee = match transcript.as_deref().map(tee::Tee::start).transpose() {
+        Ok(tee) => tee,
+        Err(err) => return report_error(err),
+    };
+    let status = action().unwrap_or_else(report_error);
+    drop(tee);
+    status
+}
+
+fn report_error(err: anyhow::Error) -> RunStatus {
+    eprintln!("Error: {err:?}");
+    if interrupt::requested() {
+        return RunStatus::Interrupted;
+    }
+    err.chain()
+        .find_map(|cause| cause.downcast_ref::<ManifestError>())
+        .map_or(RunStatus::HarnessError, ManifestError::status)
+}
+
+fn run(cli: Cli) -> Result<RunStatus> {
+    if cli.print_schema {
+        print_manifest_schema()?;
+        return Ok(RunStatus::Passed);
+    }
     let workspace = fs::canonicalize(&cli.workspace)
         .with_context(|| format!("failed to resolve workspace {}", cli.workspace.display()))?;
+    if cli.list_suites {
+        list_suites(&workspace);
+        return Ok(RunStatus::Passed);
+    }
+    if cli.doctor {
+        return Ok(doctor::run(&workspace));
+    }
+    let suite = cli
+        .suite
+        .expect("clap requires a suite (or STARRY_SUITE) unless a suite-independent flag is given");
+    let suite = SuiteSpec::resolve(suite, cli.suite_dir.as_deref(), cli.display_name.as_deref())?;
+    if cli.preflight {
+        return preflight(&suite, &workspace, &cli.run);
+    }
 
     match cli.action {
-        Action::Run => run_suite(cli.suite, &workspace),
+        Action::Run if cli.run.watch => watch::run(&suite, &workspace, &cli.run),
+        Action::Run if cli.run.bisect => bisect::run(&suite, &workspace, &cli.run, &clock::SystemClock),
+        Action::Run if cli.run.retry_suite > 0 => retry::run(&suite, &workspace, &cli.run, &clock::SystemClock),
+        Action::Run => run_suite(&suite, &workspace, &cli.run, &clock::SystemClock),
+        Action::List => list_cases(&suite, &workspace, &cli.run),
+        Action::ListArtifacts => {
+            let logs_root = logs_root(&cli.run, &suite, &workspace)?;
+            artifacts::list(&logs_root, &workspace, &cli.run_id, cli.open)
+        }
+        Action::DumpEnv => dump_env(&suite, &workspace, &cli.run),
+        Action::FailuresLog => {
+            let logs_root = logs_root(&cli.run, &suite, &workspace)?;
+            failures::write(&logs_root, &workspace, &cli.run_id)
+        }
+        Action::SuggestTimeouts => {
+            let selection = select_cases(&suite, &workspace, &cli.run)?;
+            let logs_root = logs_root(&cli.run, &suite, &workspace)?;
+            timeouts::suggest(
+                &logs_root,
+                &selection.cases,
+                selection.manifest.default_timeout_secs,
+                cli.history_runs,
+            )
+        }
     }
 }
 
@@ -29,15 +138,210 @@ fn main() -> Result<()> {
 #[command(
     name = "starry-test-harness",
     version,
-    about = "Rust harness for Starry OS test suites"
+    about = "Rust harness for Starry OS test suites",
+    after_help = "To run a single command through the harness, see `starry-test-harness exec --help`."
 )]
 struct Cli {
-    #[arg(value_enum)]
-    suite: Suite,
+    /// Suite to run; falls back to the STARRY_SUITE environment variable
+    #[arg(value_enum, env = "STARRY_SUITE", required_unless_present_any = ["print_schema", "list_suites", "doctor"])]
+    suite: Option<Suite>,
     #[arg(value_enum, default_value = "run")]
     action: Action,
     #[arg(long, default_value = ".")]
     workspace: PathBuf,
+    /// Directory under tests/ holding the `custom` suite's suite.toml
+    #[arg(long, value_name = "NAME", required_if_eq("suite", "custom"))]
+    suite_dir: Option<String>,
+    /// Name shown in output and logs (defaults to the suite's own name)
+    #[arg(long, value_name = "NAME")]
+    display_name: Option<String>,
+    #[command(flatten)]
+    run: RunOptions,
+    /// Print the JSON Schema of suite.toml and exit
+    #[arg(long, hide = true)]
+    print_schema: bool,
+    /// List the known suites and whether their suite.toml exists, then exit
+    #[arg(long)]
+    list_suites: bool,
+    /// Check every suite.toml under tests/ for parse errors, missing scripts and
+    /// colliding case names without running anything
+    #[arg(long)]
+    doctor: bool,
+    /// Check that the suite's required_tools (and runner) are on PATH, then exit
+    #[arg(long)]
+    preflight: bool,
+    /// With list-artifacts or failures-log: the run id (e.g. 20250107-093000), or `latest`
+    #[arg(long, value_name = "ID", default_value = "latest")]
+    run_id: String,
+    /// With list-artifacts: print only absolute paths, one per line, ready for scp
+    #[arg(long)]
+    open: bool,
+    /// With suggest-timeouts: how many of the most recent runs to read
+    #[arg(long, value_name = "N", default_value_t = 10, value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..))]
+    history_runs: usize,
+}
+
+#[derive(Args, Debug, Clone)]
+struct RunOptions {
+    /// Scale every effective case timeout, e.g. 4.0 when running under QEMU TCG
+    #[arg(long, default_value_t = 1.0, value_parser = parse_timeout_multiplier)]
+    timeout_multiplier: f64,
+    /// Fail a case when it leaves processes behind in its process group
+    #[arg(long)]
+    strict_orphans: bool,
+    /// Load the suite from this suite.toml instead of tests/<suite>/suite.toml
+    #[arg(long, value_name = "PATH")]
+    manifest: Option<PathBuf>,
+    /// Only run cases affected by files changed since BASE (default origin/main)
+    #[arg(long, value_name = "BASE", num_args = 0..=1, default_missing_value = "origin/main")]
+    only_changed: Option<String>,
+    /// After the run, report the N slowest cases
+    #[arg(long, value_name = "N")]
+    top_slow: Option<usize>,
+    /// With --top-slow, flag cases at least this many percent slower than the previous run
+    #[arg(long, value_name = "PCT", default_value_t = 50.0)]
+    slowdown_threshold: f64,
+    /// If the build fails, mark every case skipped and still write a summary
+    #[arg(long)]
+    keep_going: bool,
+    /// Capture stderr through the stdout pipe so case logs keep the real ordering
+    #[arg(long)]
+    merge_output: bool,
+    /// Load KEY=VALUE lines from PATH into every case's environment, beneath manifest `env`
+    #[arg(long, value_name = "PATH")]
+    env_file: Option<PathBuf>,
+    /// How often a running case is polled for exit; lower detects exits and
+    /// timeouts sooner, higher costs less CPU on large runs
+    #[arg(
+        long,
+        value_name = "MS",
+        default_value_t = 50,
+        value_parser = clap::builder::RangedU64ValueParser::<u64>::new().range(1..=1000)
+    )]
+    poll_interval_ms: u64,
+    /// Fail any case that writes to stderr, even when it exits 0
+    #[arg(long, conflicts_with = "merge_output")]
+    fail_on_stderr: bool,
+    /// Prefix every line of suite.log and the case logs with an ISO-8601 timestamp
+    #[arg(long)]
+    timestamps: bool,
+    /// Embed the last N lines of each failed case's log in the summary
+    #[arg(long, value_name = "N")]
+    log_tail: Option<usize>,
+    /// Show a ready-to-paste shell command reproducing each failed case (cwd,
+    /// environment and command line) in the summary and the case log
+    #[arg(long)]
+    print_failing_command: bool,
+    /// Print each selected case's resolved command, timeout and environment as JSON, without running
+    #[arg(long)]
+    explain: bool,
+    /// After the run, report cases that regressed or got fixed since an earlier run
+    #[arg(long)]
+    compare: bool,
+    /// With --compare, diff against N runs ago or the first run at/after a timestamp
+    /// (e.g. 3, 20250107, 2025-01-07T09:30:00) instead of the previous run
+    #[arg(long, value_name = "WHEN", requires = "compare", value_parser = history::parse_since)]
+    since: Option<history::Since>,
+    /// Serve live progress as JSON at http://ADDR/status and /cases while the suite runs
+    #[arg(long, value_name = "ADDR")]
+    serve: Option<String>,
+    /// After the run, write node_exporter textfile metrics (starry_tests_*) to PATH
+    #[arg(long, value_name = "PATH")]
+    metrics_file: Option<PathBuf>,
+    /// Print one line per finished case instead of the multi-line case boxes
+    #[arg(long)]
+    compact: bool,
+    /// Characters for status marks, case boxes and rules
+    #[arg(long, value_enum, value_name = "STYLE", default_value = "unicode")]
+    glyphs: Glyphs,
+    /// Same as --glyphs ascii, for terminals and log collectors that mangle Unicode
+    #[arg(long, conflicts_with = "glyphs")]
+    ascii: bool,
+    /// Put case artifacts under DIR/<suite>/<run id> instead of next to the logs
+    #[arg(long, value_name = "DIR")]
+    artifact_dir: Option<PathBuf>,
+    /// Stop running cases once N have hard-failed; the rest are marked skipped
+    #[arg(long, value_name = "N", value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..))]
+    bail_after: Option<usize>,
+    /// Wait this many milliseconds between launching successive cases
+    #[arg(long, value_name = "MS", default_value_t = 0)]
+    start_delay_ms: u64,
+    /// Run the selected cases in random order
+    #[arg(long, conflicts_with = "shuffle_groups")]
+    shuffle: bool,
+    /// Shuffle the order of groups and of cases within each group, keeping groups contiguous
+    #[arg(long)]
+    shuffle_groups: bool,
+    /// Seed for --shuffle / --shuffle-groups (printed and recorded when omitted)
+    #[arg(long, value_name = "N")]
+    seed: Option<u64>,
+    /// Derive the shuffle seed from TEXT (e.g. the commit SHA), so every run of
+    /// one commit gets the same order and different commits get different ones
+    #[arg(long, value_name = "TEXT", conflicts_with = "seed")]
+    seed_from: Option<String>,
+    /// Run every case command under the manifest `tracer` (default `strace -f`),
+    /// writing the trace into the case artifact directory; skipped with a warning
+    /// when the tracer is not installed
+    #[arg(long)]
+    trace_syscalls: bool,
+    /// Save the last /proc/<pid>/status and smaps_rollup of each case command
+    /// before it ends into its artifact directory (Linux only)
+    #[arg(long)]
+    proc_snapshot: bool,
+    /// After the run, re-run affected cases whenever the manifest or a case script changes
+    #[arg(long, conflicts_with = "explain")]
+    watch: bool,
+    /// Write run logs and last_run.json under DIR/<suite> instead of <workspace>/logs
+    #[arg(long, value_name = "DIR")]
+    log_dir: Option<PathBuf>,
+    /// How to report the finished run: the console summary, or the summary JSON on stdout
+    #[arg(long, value_enum, default_value = "text")]
+    format: OutputFormat,
+    /// Which run directories (logs and artifacts) to keep once the run is over;
+    /// last_run.json is written either way
+    #[arg(long, value_enum, value_name = "WHEN", default_value = "always")]
+    keep: KeepRun,
+    /// After the run, write a Chrome trace (chrome://tracing, Perfetto) of the build and cases to PATH
+    #[arg(long, value_name = "PATH")]
+    trace: Option<PathBuf>,
+    /// Also write a plain-text copy of the console output (no colors or redraws) to PATH
+    #[arg(long, value_name = "PATH")]
+    tee: Option<PathBuf>,
+    /// Warn, and exit 3 if the run otherwise passed, when the suite takes longer than SECS
+    #[arg(long, value_name = "SECS", value_parser = clap::builder::RangedU64ValueParser::<u64>::new().range(1..))]
+    max_duration_secs: Option<u64>,
+    /// With --max-duration-secs, only warn; the exit code ignores the run time
+    #[arg(long, requires = "max_duration_secs")]
+    max_duration_warn_only: bool,
+    /// Fail the run when any case was skipped, so a broken setup, --bail-after
+    /// or skip_exit_code cannot quietly shrink coverage
+    #[arg(long)]
+    no_skips_allowed: bool,
+    /// Pass the run when at least PERCENT of the cases that ran passed, even
+    /// with failures; below it the run fails. Skipped cases are not counted
+    #[arg(long, value_name = "PERCENT", value_parser = parse_pass_rate)]
+    min_pass_rate: Option<f64>,
+    /// With --min-pass-rate, count soft and flaky failures as passes instead of failures
+    #[arg(long, requires = "min_pass_rate")]
+    pass_rate_allows_soft_fails: bool,
+    /// When the suite ends with failed cases, re-run the whole suite up to N more
+    /// times; a case only counts as failed if it failed in every attempt
+    #[arg(long, value_name = "N", default_value_t = 0, conflicts_with = "watch")]
+    retry_suite: u32,
+    /// When the selected order fails (e.g. a --shuffle with the failing --seed),
+    /// re-run subsets of it to find the earlier case that breaks the first failure
+    #[arg(long, conflicts_with_all = ["watch", "retry_suite"])]
+    bisect: bool,
+    /// Write a Markdown report to PATH only when cases flipped between passing and
+    /// failing since the previous run (or --since); the first run always writes it
+    #[arg(long, value_name = "PATH")]
+    notify_on_change: Option<PathBuf>,
+}
+
+impl RunOptions {
+    fn glyphs(&self) -> Glyphs {
+        if self.ascii { Glyphs::Ascii } else { self.glyphs }
+    }
 }
 
 #[derive(Clone, Copy, Debug, ValueEnum)]
@@ -50,68 +354,405 @@ enum Suite {
     StressTest,
     #[value(name = "daily-test")]
     DailyTest,
+    /// Any tests/<NAME>/suite.toml, selected with --suite-dir
+    Custom,
 }
 
 impl Suite {
-    fn dir_name(&self) -> &'static str {
-        match self {
-            Suite::CiTest => "ci",
-            Suite::CiTestIter => "ci-test-iter",
-            Suite::StressTest => "stress",
-            Suite::DailyTest => "daily",
-        }
+    /// Directory and display name of a built-in suite; `None` for `custom`,
+    /// which takes both from the command line.
+    fn builtin(self) -> Option<SuiteSpec> {
+        let (dir_name, display_name) = match self {
+            Suite::CiTest => ("ci", "CI Test"),
+            Suite::CiTestIter => ("ci-test-iter", "CI Iteration Test"),
+            Suite::StressTest => ("stress", "Stress Test"),
+            Suite::DailyTest => ("daily", "Daily Test"),
+            Suite::Custom => return None,
+        };
+        Some(SuiteSpec {
+            dir_name: dir_name.to_string(),
+            display_name: display_name.to_string(),
+        })
     }
+}
 
-    fn display_name(&self) -> &'static str {
-        match self {
-            Suite::CiTest => "CI Test",
-            Suite::CiTestIter => "CI Iteration Test",
-            Suite::StressTest => "Stress Test",
-            Suite::DailyTest => "Daily Test",
+/// The suite a run operates on, built-in or custom.
+#[derive(Clone, Debug)]
+struct SuiteSpec {
+    dir_name: String,
+    display_name: String,
+}
+
+impl SuiteSpec {
+    fn resolve(suite: Suite, suite_dir: Option<&str>, display_name: Option<&str>) -> Result<Self> {
+        let mut spec = match suite.builtin() {
+            Some(spec) => spec,
+            None => {
+                let dir = suite_dir.context("the custom suite requires --suite-dir <NAME>")?;
+                if dir.is_empty() || dir == "." || dir == ".." || dir.contains(['/', '\\']) {
+                    bail!("--suite-dir must be a directory name under tests/, got `{dir}`");
+                }
+                SuiteSpec {
+                    dir_name: dir.to_string(),
+                    display_name: dir.to_string(),
+                }
+            }
+        };
+        if let Some(name) = display_name {
+            spec.display_name = name.to_string();
         }
+        Ok(spec)
+    }
+
+    fn dir_name(&self) -> &str {
+        &self.dir_name
+    }
+
+    fn display_name(&self) -> &str {
+        &self.display_name
     }
 }
 
 #[derive(Clone, Copy, Debug, ValueEnum)]
 enum Action {
     Run,
+    /// Print the selected cases without running them
+    List,
+    /// List the files a past run (--run-id) collected, with sizes and owning case
+    ListArtifacts,
+    /// Print the STARRY_* variables a case script gets, as shell assignments
+    DumpEnv,
+    /// Concatenate the logs of a past run's (--run-id) failed cases into its failures.log
+    FailuresLog,
+    /// Suggest each case's timeout_secs from its longest passing duration in recent runs
+    SuggestTimeouts,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum KeepRun {
+    Always,
+    /// Keep runs with failures, soft failures, a setup error or an interruption
+    OnFailure,
+    Never,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    Text,
+    Json,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
 struct Manifest {
     name: Option<String>,
     description: Option<String>,
     build_script: Option<String>,
+    /// Extra attempts for a failing build script (network hiccups and the like).
+    #[serde(default)]
+    build_retries: u32,
+    /// Delay before the first build retry; doubles on every further attempt.
+    #[serde(default = "default_build_retry_delay")]
+    build_retry_delay_secs: u64,
     arch: Option<String>,
     #[serde(default = "default_timeout")]
     default_timeout_secs: u64,
+    /// Exit code by which a case reports itself skipped (POSIX tests use 77),
+    /// unless the case lists it in `allowed_exit_codes`.
+    skip_exit_code: Option<i32>,
+    /// Extra attempts for every failing case that does not set its own `retries`.
+    #[serde(default)]
+    default_retries: u32,
+    /// Interpreter every case script and the build script run through (e.g.
+    /// `bash`), instead of their shebang lines. Bare names are looked up on
+    /// `PATH`, anything with a slash is workspace-relative.
+    shell: Option<String>,
+    /// Directories (workspace-relative or absolute) put in front of `PATH`
+    /// for the build and every case, e.g. a freshly built toolchain's `bin`.
+    #[serde(default)]
+    path_prepend: Vec<String>,
+    /// Programs the suite needs, e.g. `qemu-system-aarch64` or `mkfs.ext4`.
+    /// They are looked up on `PATH` (after `path_prepend`) before the build,
+    /// so a missing one fails the run up front instead of every case.
+    #[serde(default)]
+    required_tools: Vec<String>,
+    /// Wraps every case command, e.g. `qemu-system-aarch64`; the case path and
+    /// args are then interpreted inside the guest.
+    runner: Option<String>,
+    /// Program `--trace-syscalls` runs every case command under.
+    tracer: Option<TracerConfig>,
+    /// Arguments for `runner`; `{command}` is replaced by the case command line
+    /// (appended as the last argument when no placeholder is present).
+    #[serde(default)]
+    runner_args: Vec<String>,
+    /// Suite-wide soft threshold; see `TestCase::warn_after_secs`.
+    default_warn_after_secs: Option<u64>,
+    /// Suite-wide output stall limit; see `TestCase::stall_timeout_secs`.
+    default_stall_timeout_secs: Option<u64>,
+    /// Extra time the runner gets on top of the case budget to boot the guest.
+    #[serde(default = "default_boot_timeout")]
+    boot_timeout_secs: u64,
+    /// Output line marking a passing case (regex, default `^PASS:`).
+    #[serde(default = "default_success_pattern")]
+    success_pattern: String,
+    /// Output line marking a failing case (regex, default `^FAIL:`).
+    #[serde(default = "default_failure_pattern")]
+    failure_pattern: String,
+    /// Log patterns that label failed cases for triage; the first match wins.
+    #[serde(default)]
+    failure_classifiers: Vec<FailureClassifier>,
+    /// Environment for every case, on top of `--env-file`.
+    #[serde(default)]
+    env: BTreeMap<String, String>,
+    /// Rewrites applied to every case's stdout before its `checks` run.
+    #[serde(default)]
+    normalize: Vec<checks::Normalize>,
     #[serde(default)]
     cases: Vec<TestCase>,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Clone, JsonSchema)]
+struct TracerConfig {
+    /// Bare names are looked up on `PATH`, anything with a slash is workspace-relative.
+    #[serde(default = "default_tracer")]
+    program: String,
+    /// Arguments before the case command; `{output}` is replaced by the trace
+    /// file in the case artifact directory.
+    #[serde(default = "default_tracer_args")]
+    args: Vec<String>,
+}
+
+impl Default for TracerConfig {
+    fn default() -> Self {
+        Self {
+            program: default_tracer(),
+            args: default_tracer_args(),
+        }
+    }
+}
+
+fn default_tracer() -> String {
+    "strace".into()
+}
+
+fn default_tracer_args() -> Vec<String> {
+    ["-f", "-o", TRACER_OUTPUT_PLACEHOLDER].map(String::from).to_vec()
+}
+
+#[derive(Debug, Deserialize, Clone, JsonSchema)]
+struct FailureClassifier {
+    /// Regex searched for in a failed case's log.
+    pattern: String,
+    /// Category reported for matching cases (e.g. `panic`, `segfault`).
+    label: String,
+}
+
+/// How a case's captured output is logged and inspected.
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+enum OutputEncoding {
+    /// Text; output is logged as is and invalid UTF-8 gets a warning.
+    #[default]
+    Utf8,
+    /// Text that may contain stray bytes; the log gets U+FFFD in their place.
+    Lossy,
+    /// Raw bytes (memory dumps, images); logged untouched, without output
+    /// checks, sentinels or subtest parsing.
+    Binary,
+}
+
+#[derive(Debug, Deserialize, Clone, JsonSchema)]
 struct TestCase {
     name: String,
+    /// Stable identifier that survives renames: when set it names the case's
+    /// logs and artifacts and matches it against earlier runs instead of `name`.
+    id: Option<String>,
     description: Option<String>,
     path: String,
     #[serde(default)]
     args: Vec<String>,
     timeout_secs: Option<u64>,
+    /// Log a "still running" note once the case runs this long, without killing it.
+    warn_after_secs: Option<u64>,
+    /// Kill the case once stdout and stderr have both been silent this long,
+    /// and report it as stalled rather than timed out.
+    stall_timeout_secs: Option<u64>,
+    /// File (workspace-relative) piped to the case's stdin.
+    stdin_file: Option<String>,
+    /// Script (workspace-relative) run just before the case command, with the
+    /// same environment and working directory. If it fails, the command is not
+    /// run and the case fails.
+    before_script: Option<String>,
+    /// Script (workspace-relative) run after the case command, even when it
+    /// failed, for cleanup. Its failure is logged but does not fail the case.
+    after_script: Option<String>,
+    /// Area the case belongs to (e.g. `signals`, `fs`), for grouped summaries.
+    group: Option<String>,
+    /// Capture stdout and stderr as one chronologically ordered stream.
+    #[serde(default)]
+    merge_output: bool,
+    /// Fail the case if it writes anything to stderr, even with a zero exit.
+    #[serde(default)]
+    fail_on_stderr: bool,
+    /// What the case writes: `utf8` text (the default), `lossy` text with
+    /// invalid bytes replaced in the log, or `binary` data.
+    #[serde(default)]
+    output_encoding: OutputEncoding,
+    /// Environment for this case, on top of the suite `env`. The harness's own
+    /// `STARRY_*` variables always win.
+    #[serde(default)]
+    env: BTreeMap<String, String>,
+    /// Run with the working directory set to the case artifact directory, so
+    /// files the case drops are collected with it.
+    #[serde(default)]
+    run_in_artifact_dir: bool,
+    /// Pause before launching this case, on top of `--start-delay-ms`.
+    #[serde(default)]
+    delay_before_ms: u64,
+    /// Cases run phase by phase in ascending order (default 0): a phase
+    /// starts only once every case of the phases before it has finished.
+    #[serde(default)]
+    phase: u32,
     #[serde(default)]
     allow_failure: bool,
+    /// Known to fail intermittently: a failure is reported as `flaky_failed`,
+    /// which does not fail the run but is counted apart from `allow_failure`.
+    #[serde(default)]
+    flaky: bool,
+    /// Exit codes that count as a pass (e.g. `[0, 77]` where 77 means
+    /// "unsupported"). When set, any other code fails the case.
+    #[serde(default)]
+    allowed_exit_codes: Vec<i32>,
+    /// Signal number the case must die from (e.g. 11 for a SIGSEGV negative
+    /// test); a normal exit or any other signal fails the case.
+    expected_signal: Option<i32>,
+    /// Extra attempts for a failing case; the last attempt decides the outcome.
+    /// Overrides the suite's `default_retries`.
+    retries: Option<u32>,
+    /// Only retry when the case exits with one of these codes.
+    #[serde(default)]
+    retry_on_exit_codes: Vec<i32>,
+    /// Only retry when the case is killed by one of these signals. With both
+    /// lists empty, any failure is retried.
+    #[serde(default)]
+    retry_on_signals: Vec<i32>,
+    /// Why `allowed_exit_codes` or `allow_failure` is acceptable; logged
+    /// whenever one of them decides the outcome.
+    allow_reason: Option<String>,
+    /// Globs (workspace-relative) whose changes select this case under `--only-changed`.
+    #[serde(default)]
+    sources: Vec<String>,
+    /// Assertions on output and exit code (`[[cases.checks]]`); all must pass.
+    #[serde(default)]
+    checks: Vec<checks::Check>,
+    /// Rewrites applied to stdout before `checks` run, after the suite's own
+    /// `normalize` rules. The log keeps the raw output.
+    #[serde(default)]
+    normalize: Vec<checks::Normalize>,
 }
 
-#[derive(Debug, Serialize)]
+impl TestCase {
+    /// File-name-safe form of the `id`, or of the name without one.
+    fn slug(&self) -> String {
+        sanitize_case_name(self.id.as_deref().unwrap_or(&self.name))
+    }
+
+    /// Catches settings that cannot work before anything is built or run.
+    fn validate(&self) -> Result<()> {
+        if self.expected_signal.is_some() && !self.allowed_exit_codes.is_empty() {
+            bail!(
+                "case {}: expected_signal and allowed_exit_codes cannot both be set",
+                self.name
+            );
+        }
+        if self.flaky && self.allow_failure {
+            bail!(
+                "case {}: flaky and allow_failure cannot both be set (flaky marks a bug, allow_failure a design choice)",
+                self.name
+            );
+        }
+        if self.fail_on_stderr && self.merge_output {
+            bail!(
+                "case {}: fail_on_stderr cannot be combined with merge_output (stderr is folded into stdout)",
+                self.name
+            );
+        }
+        if let Some(id) = &self.id
+            && sanitize_case_name(id).is_empty()
+        {
+            bail!("case {}: id `{id}` has no letters or digits to name its logs by", self.name);
+        }
+        checks::compile(&self.name, &self.checks)?;
+        checks::compile_normalize(&format!("case {}", self.name), &self.normalize)?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
 struct CaseDetail {
     name: String,
+    /// The manifest `id`, which history matches on instead of the name.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
+    /// The manifest `description`, so reports can show what a case is for.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    group: Option<String>,
     status: String,
+    /// Milliseconds between the suite start and this case starting.
+    started_offset_ms: u128,
     duration_ms: u128,
     exit_code: Option<i32>,
     allow_failure: bool,
     log_path: PathBuf,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    orphaned_pids: Vec<i32>,
+    /// Core files moved into the case artifact directory.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    core_dumps: Vec<PathBuf>,
+    /// Last lines of the case log for failed cases, with `--log-tail`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    log_tail: Option<Vec<String>>,
+    /// Shell command reproducing a failed case, with `--print-failing-command`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    failing_command: Option<String>,
+    /// Runs it took, counting retries.
+    #[serde(default = "single_attempt", skip_serializing_if = "is_single_attempt")]
+    attempts: u32,
+    /// Logs of the earlier, failed attempts.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    attempt_logs: Vec<PathBuf>,
+    /// Label of the first `failure_classifiers` entry matching a failed case's log.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    failure_class: Option<String>,
+    /// First stderr line of a case failed by `fail_on_stderr`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stderr_line: Option<String>,
+    /// Why a skipped case did not run or reported itself skipped.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    skip_reason: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
+impl CaseDetail {
+    /// What history matches the case on across runs: its `id`, else its name.
+    fn key(&self) -> &str {
+        self.id.as_deref().unwrap_or(&self.name)
+    }
+}
+
+/// `cases/<slug>.json`: one case's detail plus how it was launched, written
+/// as soon as the case finishes so interrupted runs stay analyzable.
+#[derive(Serialize)]
+struct CaseResultFile<'a> {
+    #[serde(flatten)]
+    detail: &'a CaseDetail,
+    command: Vec<String>,
+    cwd: PathBuf,
+    env: BTreeMap<String, String>,
+    artifact_dir: PathBuf,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
 struct RunSummary {
     suite: String,
     action: String,
@@ -123,11 +764,157 @@ struct RunSummary {
     passed: usize,
     failed: usize,
     soft_failed: usize,
+    /// Failures of cases marked `flaky`; like soft failures they do not fail the run.
+    flaky_failed: usize,
+    skipped: usize,
+    /// Why setup failed when `--keep-going` skipped every case.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    setup_error: Option<String>,
+    /// The run was cut short; `cases` only lists what actually ran.
+    interrupted: bool,
+    /// Failure count at which `--bail-after` skipped the remaining cases.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bailed_after: Option<usize>,
     log_file: PathBuf,
     error_log: Option<PathBuf>,
     case_logs_root: PathBuf,
     artifacts_root: PathBuf,
     cases: Vec<CaseDetail>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    slowest_cases: Vec<history::CaseTiming>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    slowed_down_cases: Vec<history::CaseTiming>,
+    /// Per-group breakdown; empty when no case sets `group`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    groups: Vec<GroupSummary>,
+    /// Failed cases per `failure_classifiers` label (`unclassified` when none matched).
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    failure_classes: BTreeMap<String, usize>,
+    /// Seeds behind a randomized order, with `--shuffle` / `--shuffle-groups`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    shuffle: Option<shuffle::ShuffleInfo>,
+    /// Status changes against an earlier run, with `--compare`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    comparison: Option<history::Comparison>,
+    /// The suite's build step; absent for `exec` runs, which have none.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    build: Option<BuildDetail>,
+    /// Run time against `--max-duration-secs`, when given.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_duration: Option<MaxDuration>,
+    /// Pass rate against `--min-pass-rate`, when given.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pass_rate: Option<PassRate>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct BuildDetail {
+    /// Build script, relative to the workspace.
+    script: String,
+    /// The script does not exist, so nothing was built.
+    skipped: bool,
+    /// Milliseconds between the suite start and the build starting.
+    started_offset_ms: u128,
+    /// Wall time of every attempt, retry delays included.
+    duration_ms: u128,
+    /// Exit code of the last attempt; `None` when skipped or killed by a signal.
+    exit_code: Option<i32>,
+    attempts: u32,
+    success: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct MaxDuration {
+    limit_secs: u64,
+    duration_secs: f64,
+    exceeded: bool,
+    /// Exceeding it only warns (`--max-duration-warn-only`).
+    warn_only: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct PassRate {
+    /// Percentage of the cases that ran which passed; 0 when none ran.
+    rate: f64,
+    min_rate: f64,
+    /// Cases counted as passing, out of `counted` (every case but the skipped).
+    passing: usize,
+    counted: usize,
+    /// Soft and flaky failures counted as passes (`--pass-rate-allows-soft-fails`).
+    soft_fails_pass: bool,
+    met: bool,
+}
+
+impl PassRate {
+    fn new(results: &results::CaseResults, min_rate: f64, soft_fails_pass: bool) -> Self {
+        let soft = results.soft_failed + results.flaky_failed;
+        let passing = results.passed + if soft_fails_pass { soft } else { 0 };
+        let counted = results.passed + results.failed + soft;
+        let rate = if counted == 0 { 0.0 } else { passing as f64 * 100.0 / counted as f64 };
+        Self {
+            rate,
+            min_rate,
+            passing,
+            counted,
+            soft_fails_pass,
+            met: counted > 0 && rate >= min_rate,
+        }
+    }
+}
+
+/// Bucket for cases without a `group` once any case has one.
+const UNGROUPED: &str = "ungrouped";
+
+/// `failure_class` of a case killed by `stall_timeout_secs` that no
+/// `failure_classifiers` entry matched.
+const STALLED_CLASS: &str = "stalled";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct GroupSummary {
+    name: String,
+    total: usize,
+    passed: usize,
+    failed: usize,
+    soft_failed: usize,
+    flaky_failed: usize,
+    duration_ms: u128,
+}
+
+/// Aggregates cases by `group`, in order of first appearance.
+fn group_summaries(cases: &[CaseDetail]) -> Vec<GroupSummary> {
+    if cases.iter().all(|case| case.group.is_none()) {
+        return Vec::new();
+    }
+    let mut groups: Vec<GroupSummary> = Vec::new();
+    for case in cases {
+        let name = case.group.as_deref().unwrap_or(UNGROUPED);
+        let idx = match groups.iter().position(|group| group.name == name) {
+            Some(idx) => idx,
+            None => {
+                groups.push(GroupSummary {
+                    name: name.to_string(),
+                    total: 0,
+                    passed: 0,
+                    failed: 0,
+                    soft_failed: 0,
+                    flaky_failed: 0,
+                    duration_ms: 0,
+                });
+                groups.len() - 1
+            }
+        };
+        let group = &mut groups[idx];
+        group.total += 1;
+        group.duration_ms += case.duration_ms;
+        match case.status.as_str() {
+            "passed" => group.passed += 1,
+            "failed" => group.failed += 1,
+            "soft_failed" => group.soft_failed += 1,
+            "flaky_failed" => group.flaky_failed += 1,
+            _ => {}
+        }
+    }
+    groups
 }
 
 #[derive(Debug)]
@@ -135,8 +922,22 @@ struct CaseOutcome {
     status: CaseStatus,
     duration_ms: u128,
     exit_code: Option<i32>,
+    timeout_secs: u64,
     log_path: PathBuf,
     failed_details: Option<Vec<FailedSubCaseDetail>>,
+    orphaned_pids: Vec<i32>,
+    core_dumps: Vec<PathBuf>,
+    /// Signal that killed the command, if any.
+    signal: Option<i32>,
+    /// Expected-vs-actual diffs for failed text checks.
+    check_diffs: Vec<diff::Diff>,
+    /// First stderr line when `fail_on_stderr` failed the case.
+    stderr_line: Option<String>,
+    skip_reason: Option<String>,
+    /// The `stall_timeout_secs` the case was killed at for going silent.
+    stalled_after_secs: Option<u64>,
+    /// With `--print-failing-command`, how to rerun a failed case by hand.
+    failing_command: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -150,6 +951,64 @@ enum CaseStatus {
     Passed,
     Failed,
     SoftFailed,
+    /// Failed, but the case is marked `flaky`.
+    FlakyFailed,
+    /// Killed because the run was interrupted.
+    Interrupted,
+    /// Never started, e.g. because setup failed under `--keep-going`, or
+    /// exited with the suite's `skip_exit_code`.
+    Skipped,
+}
+
+/// Process exit code of the `run` action, so CI can tell "tests failed"
+/// apart from "the harness itself broke".
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum RunStatus {
+    /// Every case passed; soft failures (`allow_failure`) are tolerated.
+    Passed,
+    /// At least one case failed.
+    TestsFailed,
+    /// The run could not proceed: bad manifest, failed build, I/O errors.
+    HarnessError,
+    /// Stopped early by Ctrl-C or SIGTERM; the summary is partial.
+    Interrupted,
+    /// Every case passed but the run took longer than `--max-duration-secs`.
+    TooSlow,
+    /// There is no `suite.toml` where the suite expects one.
+    ManifestNotFound,
+    /// `suite.toml` exists but is not valid TOML or not a valid manifest.
+    ManifestInvalid,
+    /// `suite.toml` parses but defines no cases.
+    ManifestEmpty,
+}
+
+impl RunStatus {
+    fn from_failures(failed: usize) -> Self {
+        if failed == 0 {
+            RunStatus::Passed
+        } else {
+            RunStatus::TestsFailed
+        }
+    }
+
+    fn code(self) -> u8 {
+        match self {
+            RunStatus::Passed => 0,
+            RunStatus::TestsFailed => 1,
+            RunStatus::HarnessError => 2,
+            RunStatus::TooSlow => 3,
+            RunStatus::ManifestNotFound => 4,
+            RunStatus::ManifestInvalid => 5,
+            RunStatus::ManifestEmpty => 6,
+            RunStatus::Interrupted => interrupt::EXIT_CODE,
+        }
+    }
+}
+
+impl From<RunStatus> for ExitCode {
+    fn from(status: RunStatus) -> Self {
+        ExitCode::from(status.code())
+    }
 }
 
 impl CaseStatus {
@@ -158,6 +1017,9 @@ impl CaseStatus {
             CaseStatus::Passed => "passed",
             CaseStatus::Failed => "failed",
             CaseStatus::SoftFailed => "soft_failed",
+            CaseStatus::FlakyFailed => "flaky_failed",
+            CaseStatus::Interrupted => "interrupted",
+            CaseStatus::Skipped => "skipped",
         }
     }
 }
@@ -166,32 +1028,857 @@ fn default_timeout() -> u64 {
     600
 }
 
-fn run_suite(suite: Suite, workspace: &Path) -> Result<()> {
-    let manifest = load_manifest(workspace, suite)?;
-    if manifest.cases.is_empty() {
+fn default_boot_timeout() -> u64 {
+    120
+}
+
+fn default_build_retry_delay() -> u64 {
+    5
+}
+
+/// Upper bound for the doubling build retry delay.
+const MAX_BUILD_RETRY_DELAY_SECS: u64 = 300;
+
+fn default_success_pattern() -> String {
+    r"^PASS:".into()
+}
+
+fn default_failure_pattern() -> String {
+    r"^FAIL:".into()
+}
+
+/// Placeholder in `runner_args` that receives the case command line.
+const RUNNER_COMMAND_PLACEHOLDER: &str = "{command}";
+
+/// Placeholder in the tracer `args` that receives the trace file path.
+const TRACER_OUTPUT_PLACEHOLDER: &str = "{output}";
+
+/// Smallest multiplier honoured; anything lower would starve even trivial cases.
+const MIN_TIMEOUT_MULTIPLIER: f64 = 0.1;
+
+fn parse_pass_rate(raw: &str) -> Result<f64, String> {
+    let value: f64 = raw
+        .trim_end_matches('%')
+        .parse()
+        .map_err(|_| format!("`{raw}` is not a number"))?;
+    if !(0.0..=100.0).contains(&value) {
+        return Err(format!("pass rate must be a percentage between 0 and 100, got {raw}"));
+    }
+    Ok(value)
+}
+
+fn parse_timeout_multiplier(raw: &str) -> Result<f64, String> {
+    let value: f64 = raw
+        .parse()
+        .map_err(|_| format!("`{raw}` is not a number"))?;
+    if !value.is_finite() || value <= 0.0 {
+        return Err(format!("timeout multiplier must be a positive number, got {raw}"));
+    }
+    Ok(value)
+}
+
+/// Applies the timeout multiplier to a base budget, never going below one second.
+fn scaled_timeout_secs(base_secs: u64, multiplier: f64) -> u64 {
+    ((base_secs as f64) * multiplier).ceil().max(1.0) as u64
+}
+
+/// State shared by every case invocation within a single suite run.
+struct RunContext<'a> {
+    planner: CasePlanner<'a>,
+    strict_orphans: bool,
+    merge_output: bool,
+    fail_on_stderr: bool,
+    proc_snapshot: bool,
+    poll_interval: Duration,
+    timestamps: bool,
+    sentinels: exec::Sentinels,
+    /// The suite's `normalize` rules, compiled.
+    normalize: Vec<(Regex, String)>,
+    default_warn_after_secs: Option<u64>,
+    default_stall_timeout_secs: Option<u64>,
+    skip_exit_code: Option<i32>,
+    /// Shared handle on suite.log for notes emitted while a case runs.
+    suite_log: RefCell<logfile::LogFile>,
+    /// Console lines printed while the current case runs, so the TTY box
+    /// redraw knows how far to move the cursor back up.
+    console_notes: Cell<usize>,
+    /// `--compact`: no case boxes, notes print as plain lines.
+    compact: bool,
+    glyphs: Glyphs,
+    /// `--print-failing-command`: end failed case logs with a repro command.
+    print_failing_command: bool,
+}
+
+impl RunContext<'_> {
+    /// Prints a note under the running case; boxes count them so the
+    /// redraw can move the cursor back far enough.
+    fn console_note(&self, note: &str) {
+        if self.compact {
+            println!("  {}", note.bright_yellow());
+        } else {
+            println!("{} {}", self.glyphs.box_side().bright_yellow(), note.bright_yellow());
+            self.console_notes.set(self.console_notes.get() + 1);
+        }
+    }
+}
+
+/// Turns a case into the exact command line, deadline and environment it
+/// runs with. Shared by real runs and `--explain`, so the two cannot drift.
+struct CasePlanner<'a> {
+    workspace: &'a Path,
+    run_dir: PathBuf,
+    /// Per-case artifact directories live below this (see `--artifact-dir`).
+    artifacts_root: PathBuf,
+    run_id: String,
+    default_timeout_secs: u64,
+    timeout_multiplier: f64,
+    runner: Option<CaseRunner<'a>>,
+    /// Wraps case commands with `--trace-syscalls`.
+    tracer: Option<SyscallTracer>,
+    /// Kill plain commands at their timeout too (see `Selection::adhoc`).
+    enforce_timeouts: bool,
+    /// `--env-file` entries overlaid with the manifest `env`.
+    suite_env: BTreeMap<String, String>,
+    script_env: ScriptEnv,
+}
+
+/// Where a case's log and artifacts go for this run.
+struct CasePaths {
+    slug: String,
+    log_path: PathBuf,
+    artifact_dir: PathBuf,
+}
+
+/// Everything needed to spawn one case.
+struct CasePlan {
+    program: PathBuf,
+    args: Vec<String>,
+    cwd: PathBuf,
+    base_timeout_secs: u64,
+    timeout_secs: u64,
+    /// Boot allowance under a runner; the hard deadline is boot + case budget.
+    boot_timeout_secs: Option<u64>,
+    /// The harness kills a plain command at `timeout_secs`.
+    enforce_timeout: bool,
+    /// Applied in order, so later entries win.
+    env: Vec<(String, OsString)>,
+}
+
+impl CasePlanner<'_> {
+    fn case_paths(&self, case: &TestCase) -> CasePaths {
+        let slug = case.slug();
+        CasePaths {
+            log_path: self.run_dir.join("cases").join(format!("{slug}.log")),
+            artifact_dir: self.artifacts_root.join(&slug),
+            slug,
+        }
+    }
+
+    fn plan(&self, case: &TestCase, paths: &CasePaths) -> CasePlan {
+        let base_timeout_secs = case.timeout_secs.unwrap_or(self.default_timeout_secs);
+        let timeout_secs = scaled_timeout_secs(base_timeout_secs, self.timeout_multiplier);
+        let (program, args, boot_timeout_secs) = match &self.runner {
+            Some(runner) => {
+                let mut guest_command = case.path.clone();
+                for arg in &case.args {
+                    guest_command.push(' ');
+                    guest_command.push_str(arg);
+                }
+                let boot_secs = scaled_timeout_secs(runner.boot_timeout_secs, self.timeout_multiplier);
+                (runner.program.clone(), runner.argv(&guest_command), Some(boot_secs))
+            }
+            None => {
+                let (program, args) = self.script_env.launch(self.workspace.join(&case.path), case.args.clone());
+                match &self.tracer {
+                    Some(tracer) => {
+                        let (program, args) = tracer.wrap(&paths.artifact_dir, program, args);
+                        (program, args, None)
+                    }
+                    None => (program, args, None),
+                }
+            }
+        };
+        let log_dir = paths.log_path.parent().unwrap_or_else(|| Path::new("."));
+        let harness_env: [(&str, OsString); 9] = [
+            ("STARRY_WORKSPACE_ROOT", self.workspace.into()),
+            ("STARRY_RUN_ID", self.run_id.clone().into()),
+            ("STARRY_RUN_DIR", self.run_dir.clone().into()),
+            ("STARRY_CASE_NAME", case.name.clone().into()),
+            ("STARRY_CASE_SLUG", paths.slug.clone().into()),
+            ("STARRY_CASE_LOG_PATH", paths.log_path.clone().into()),
+            ("STARRY_CASE_LOG_DIR", log_dir.into()),
+            ("STARRY_CASE_ARTIFACT_DIR", paths.artifact_dir.clone().into()),
+            ("STARRY_CASE_TIMEOUT_SECS", timeout_secs.to_string().into()),
+        ];
+        // An explicit `PATH` in `env` replaces the prepended one.
+        let mut env = self
+            .script_env
+            .path
+            .iter()
+            .map(|path| ("PATH".to_string(), path.clone()))
+            .collect::<Vec<_>>();
+        env.extend(
+            self.suite_env
+                .iter()
+                .chain(&case.env)
+                .map(|(key, value)| (key.clone(), OsString::from(value))),
+        );
+        env.extend(harness_env.into_iter().map(|(key, value)| (key.to_string(), value)));
+        CasePlan {
+            program,
+            args,
+            cwd: if case.run_in_artifact_dir {
+                paths.artifact_dir.clone()
+            } else {
+                self.workspace.to_path_buf()
+            },
+            base_timeout_secs,
+            timeout_secs,
+            boot_timeout_secs,
+            enforce_timeout: self.enforce_timeouts,
+            env,
+        }
+    }
+}
+
+impl CasePlan {
+    fn command(&self) -> Command {
+        let mut command = Command::new(&self.program);
+        command.args(&self.args).current_dir(&self.cwd);
+        for (key, value) in &self.env {
+            command.env(key, value);
+        }
+        command
+    }
+
+    /// Program followed by its arguments, as shown to humans and tooling.
+    fn argv(&self) -> Vec<String> {
+        let mut argv = vec![self.program.display().to_string()];
+        argv.extend(self.args.iter().cloned());
+        argv
+    }
+
+    /// A subshell that a developer can paste to rerun the command exactly as
+    /// the harness did: same directory, variables (in order) and stdin.
+    fn failing_command(&self, stdin: Option<&Path>) -> String {
+        let mut lines = vec!["(".to_string(), format!("  cd {} &&", shell_word(&self.cwd.to_string_lossy()))];
+        for (key, value) in &self.env {
+            lines.push(format!("  export {key}={} &&", shell_quote(&value.to_string_lossy())));
+        }
+        let mut command = format!("  {}", command_line(&self.program, &self.args));
+        if let Some(path) = stdin {
+            command.push_str(&format!(" < {}", shell_word(&path.to_string_lossy())));
+        }
+        lines.push(command);
+        lines.push(")".to_string());
+        lines.join("\n")
+    }
+
+    /// The variables the harness sets on top of its own environment.
+    fn env_map(&self) -> BTreeMap<String, String> {
+        self.env
+            .iter()
+            .map(|(key, value)| (key.clone(), value.to_string_lossy().into_owned()))
+            .collect()
+    }
+
+    /// The hard deadline the harness enforces itself (runner mode and `exec`;
+    /// plain scripts get their budget through `STARRY_CASE_TIMEOUT_SECS`).
+    fn deadline_secs(&self) -> Option<u64> {
+        match self.boot_timeout_secs {
+            Some(boot) => Some(boot + self.timeout_secs),
+            None => self.enforce_timeout.then_some(self.timeout_secs),
+        }
+    }
+}
+
+/// How scripts are launched: `Manifest::shell` and `Manifest::path_prepend`,
+/// resolved when the manifest is loaded so a missing shell fails early.
+#[derive(Debug, Clone, Default)]
+struct ScriptEnv {
+    shell: Option<PathBuf>,
+    /// `PATH` with the `path_prepend` directories in front; `None` leaves it alone.
+    path: Option<OsString>,
+}
+
+impl ScriptEnv {
+    fn resolve(manifest: &Manifest, workspace: &Path) -> Result<Self> {
+        let path = if manifest.path_prepend.is_empty() {
+            None
+        } else {
+            let inherited = env::var_os("PATH").unwrap_or_default();
+            let dirs = manifest
+                .path_prepend
+                .iter()
+                .map(|dir| workspace.join(dir))
+                .chain(env::split_paths(&inherited));
+            Some(env::join_paths(dirs).context("path_prepend entries cannot contain `:`")?)
+        };
+        let shell = match manifest.shell.as_deref() {
+            None => None,
+            Some(shell) if shell.contains('/') => {
+                let resolved = workspace.join(shell);
+                if !is_executable(&resolved) {
+                    bail!("shell {} is not an executable file", resolved.display());
+                }
+                Some(resolved)
+            }
+            Some(shell) => {
+                let search = path.clone().or_else(|| env::var_os("PATH")).unwrap_or_default();
+                Some(find_on_path(shell, &search).with_context(|| format!("shell {shell} not found on PATH"))?)
+            }
+        };
+        Ok(Self { shell, path })
+    }
+
+    /// Program and arguments that run `script`, through the shell if one is set.
+    fn launch(&self, script: PathBuf, args: Vec<String>) -> (PathBuf, Vec<String>) {
+        match &self.shell {
+            Some(shell) => {
+                let mut shell_args = vec![script.display().to_string()];
+                shell_args.extend(args);
+                (shell.clone(), shell_args)
+            }
+            None => (script, args),
+        }
+    }
+}
+
+/// The manifest's `required_tools`, plus the runner, each with where it was
+/// found: workspace-relative when it contains a slash, else on the case `PATH`.
+fn resolve_tools(manifest: &Manifest, script_env: &ScriptEnv, workspace: &Path) -> Vec<(String, Option<PathBuf>)> {
+    let search = script_env.path.clone().or_else(|| env::var_os("PATH")).unwrap_or_default();
+    let mut tools = manifest.required_tools.iter().map(String::as_str).collect::<Vec<_>>();
+    if let Some(runner) = manifest.runner.as_deref()
+        && !tools.contains(&runner)
+    {
+        tools.push(runner);
+    }
+    tools
+        .into_iter()
+        .map(|tool| {
+            let found = if tool.contains('/') {
+                Some(workspace.join(tool)).filter(|path| is_executable(path))
+            } else {
+                find_on_path(tool, &search)
+            };
+            (tool.to_string(), found)
+        })
+        .collect()
+}
+
+/// Fails when a tool from [`resolve_tools`] is missing, naming every one.
+fn check_required_tools(manifest: &Manifest, script_env: &ScriptEnv, workspace: &Path) -> Result<()> {
+    let missing = resolve_tools(manifest, script_env, workspace)
+        .into_iter()
+        .filter(|(_, found)| found.is_none())
+        .map(|(tool, _)| tool)
+        .collect::<Vec<_>>();
+    if !missing.is_empty() {
         bail!(
-            "suite {} has no cases defined - add entries to {}",
-            suite.display_name(),
-            manifest_path(workspace, suite).display()
+            "required tools not found (see required_tools / runner in suite.toml): {}",
+            missing.join(", ")
         );
     }
-    let cases = filter_cases(&manifest.cases)?;
+    Ok(())
+}
+
+/// `--preflight`: reports where each required tool resolves, without running anything.
+fn preflight(suite: &SuiteSpec, workspace: &Path, options: &RunOptions) -> Result<RunStatus> {
+    let manifest_file = resolve_manifest_path(workspace, suite, options.manifest.as_deref())?;
+    let manifest = load_manifest(&manifest_file)?;
+    let script_env = ScriptEnv::resolve(&manifest, workspace)
+        .with_context(|| format!("invalid manifest {}", manifest_file.display()))?;
+    let tools = resolve_tools(&manifest, &script_env, workspace);
+    if tools.is_empty() {
+        println!("[preflight] {} declares no required_tools", suite.display_name());
+        return Ok(RunStatus::Passed);
+    }
+    let width = tools.iter().map(|(tool, _)| tool.len()).max().unwrap_or(0);
+    let mut missing = 0;
+    let glyphs = options.glyphs();
+    for (tool, found) in &tools {
+        match found {
+            Some(path) => println!("  {} {tool:<width$}  {}", glyphs.found(true).bright_green(), path.display().to_string().dimmed()),
+            None => {
+                missing += 1;
+                println!("  {} {tool:<width$}  {}", glyphs.found(false).bright_red(), "not found".bright_red());
+            }
+        }
+    }
+    println!("[preflight] {} of {} required tools found", tools.len() - missing, tools.len());
+    Ok(if missing == 0 { RunStatus::Passed } else { RunStatus::HarnessError })
+}
+
+/// First executable `program` in the `PATH`-style list `search`.
+fn find_on_path(program: &str, search: &std::ffi::OsStr) -> Option<PathBuf> {
+    env::split_paths(search)
+        .map(|dir| dir.join(program))
+        .find(|candidate| is_executable(candidate))
+}
+
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata()
+        .is_ok_and(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+}
+
+/// The emulator wrapper configured through `Manifest::runner`.
+struct CaseRunner<'a> {
+    program: PathBuf,
+    args: &'a [String],
+    boot_timeout_secs: u64,
+}
+
+impl<'a> CaseRunner<'a> {
+    fn from_manifest(manifest: &'a Manifest, workspace: &Path) -> Option<Self> {
+        let runner = manifest.runner.as_deref()?;
+        // Bare names are looked up on PATH, anything with a slash is workspace-relative.
+        let program = if runner.contains('/') {
+            workspace.join(runner)
+        } else {
+            PathBuf::from(runner)
+        };
+        Some(Self {
+            program,
+            args: &manifest.runner_args,
+            boot_timeout_secs: manifest.boot_timeout_secs,
+        })
+    }
+
+    /// Builds the runner argv with the guest command line spliced in.
+    fn argv(&self, guest_command: &str) -> Vec<String> {
+        let mut argv = self
+            .args
+            .iter()
+            .map(|arg| arg.replace(RUNNER_COMMAND_PLACEHOLDER, guest_command))
+            .collect::<Vec<_>>();
+        if !self.args.iter().any(|arg| arg.contains(RUNNER_COMMAND_PLACEHOLDER)) {
+            argv.push(guest_command.to_string());
+        }
+        argv
+    }
+}
+
+/// The tracer `--trace-syscalls` runs case commands under.
+struct SyscallTracer {
+    program: PathBuf,
+    args: Vec<String>,
+}
+
+impl SyscallTracer {
+    /// Finds the manifest's tracer, or explains why cases run untraced: the
+    /// tracer is missing, or a runner's guest syscalls are out of its reach.
+    fn resolve(manifest: &Manifest, script_env: &ScriptEnv, workspace: &Path) -> Result<Self, String> {
+        let config = manifest.tracer.clone().unwrap_or_default();
+        if let Some(runner) = &manifest.runner {
+            return Err(format!(
+                "cases run inside runner {runner}; {} would only trace the runner, so cases run untraced",
+                config.program
+            ));
+        }
+        let program = if config.program.contains('/') {
+            Some(workspace.join(&config.program)).filter(|path| is_executable(path))
+        } else {
+            let search = script_env.path.clone().or_else(|| env::var_os("PATH")).unwrap_or_default();
+            find_on_path(&config.program, &search)
+        };
+        match program {
+            Some(program) => Ok(Self {
+                program,
+                args: config.args,
+            }),
+            None => Err(format!("tracer {} not found; cases run untraced", config.program)),
+        }
+    }
+
+    /// Trace file of a case, named after the tracer (e.g. `strace.log`).
+    fn output(&self, artifact_dir: &Path) -> PathBuf {
+        let name = self.program.file_stem().map_or_else(
+            || "trace".into(),
+            |name| name.to_string_lossy().into_owned(),
+        );
+        artifact_dir.join(format!("{name}.log"))
+    }
+
+    fn wrap(&self, artifact_dir: &Path, program: PathBuf, args: Vec<String>) -> (PathBuf, Vec<String>) {
+        let output = self.output(artifact_dir).display().to_string();
+        let mut argv = self
+            .args
+            .iter()
+            .map(|arg| arg.replace(TRACER_OUTPUT_PLACEHOLDER, &output))
+            .collect::<Vec<_>>();
+        argv.push(program.display().to_string());
+        argv.extend(args);
+        (self.program.clone(), argv)
+    }
+}
+
+/// The manifest plus the cases left after `CASES` and `--only-changed`, in
+/// run order.
+struct Selection {
+    manifest: Manifest,
+    script_env: ScriptEnv,
+    cases: Vec<TestCase>,
+    shuffle: Option<shuffle::ShuffleInfo>,
+    /// An `exec` command rather than a suite: there is no build step, and as
+    /// ad-hoc commands know nothing of `STARRY_CASE_TIMEOUT_SECS` the harness
+    /// kills them at their timeout itself.
+    adhoc: bool,
+}
+
+fn select_cases(suite: &SuiteSpec, workspace: &Path, options: &RunOptions) -> Result<Selection> {
+    let manifest_file = resolve_manifest_path(workspace, suite, options.manifest.as_deref())?;
+    let manifest = load_manifest(&manifest_file)?;
+    if manifest.cases.is_empty() {
+        return Err(ManifestError::NoCases {
+            suite: suite.display_name().to_string(),
+            path: manifest_file,
+        }
+        .into());
+    }
+    let script_env = ScriptEnv::resolve(&manifest, workspace)
+        .with_context(|| format!("invalid manifest {}", manifest_file.display()))?;
+    let mut cases = filter_cases(&manifest.cases)?;
     if cases.is_empty() {
         bail!("no test cases selected after applying CASES filter");
     }
+    if let Some(base) = &options.only_changed {
+        cases = select_changed_cases(cases, &manifest, &manifest_file, workspace, base)?;
+    }
+    let seed = match (&options.seed_from, options.seed) {
+        (Some(text), _) => shuffle::seed_from(text),
+        (None, Some(seed)) => seed,
+        (None, None) => shuffle::fresh_seed(),
+    };
+    let mut shuffle = if options.shuffle_groups {
+        let (shuffled, info) = shuffle::shuffle_groups(cases, seed);
+        cases = shuffled;
+        Some(info)
+    } else if options.shuffle {
+        Some(shuffle::shuffle_cases(&mut cases, seed))
+    } else {
+        None
+    };
+    if let Some(info) = &mut shuffle {
+        info.seed_from = options.seed_from.clone();
+    }
+    // Stable, so manifest or shuffled order is kept within each phase.
+    cases.sort_by_key(|case| case.phase);
+    Ok(Selection {
+        manifest,
+        script_env,
+        cases,
+        shuffle,
+        adhoc: false,
+    })
+}
+
+/// `<run dir>/artifacts` by default; with `--artifact-dir`, a per-suite,
+/// per-run directory below it so runs never overwrite each other.
+fn artifacts_root(options: &RunOptions, suite: &SuiteSpec, run_dir: &Path, run_id: &str) -> Result<PathBuf> {
+    match &options.artifact_dir {
+        Some(dir) => Ok(std::path::absolute(dir)
+            .with_context(|| format!("failed to resolve artifact directory {}", dir.display()))?
+            .join(suite.dir_name())
+            .join(run_id)),
+        None => Ok(run_dir.join("artifacts")),
+    }
+}
+
+/// The `--env-file` entries with the manifest `env` laid over them.
+fn suite_env(options: &RunOptions, manifest: &Manifest) -> Result<BTreeMap<String, String>> {
+    let mut env = match &options.env_file {
+        Some(path) => load_env_file(path)?,
+        None => BTreeMap::new(),
+    };
+    env.extend(manifest.env.clone());
+    Ok(env)
+}
+
+/// Parses dotenv-style `KEY=VALUE` lines. Blank lines, `#` comments and an
+/// `export ` prefix are accepted; a value wrapped in matching quotes is
+/// unquoted, anything else is taken verbatim after trimming.
+fn load_env_file(path: &Path) -> Result<BTreeMap<String, String>> {
+    let content =
+        fs::read_to_string(path).with_context(|| format!("failed to read env file {}", path.display()))?;
+    let mut env = BTreeMap::new();
+    for (idx, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let entry = line.strip_prefix("export ").unwrap_or(line);
+        let Some((key, value)) = entry.split_once('=') else {
+            bail!("{}:{}: expected KEY=VALUE, got `{line}`", path.display(), idx + 1);
+        };
+        let key = key.trim();
+        let valid_key = key.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid_key {
+            bail!("{}:{}: invalid variable name `{key}`", path.display(), idx + 1);
+        }
+        let value = value.trim();
+        let value = ['"', '\'']
+            .iter()
+            .find_map(|quote| value.strip_prefix(*quote)?.strip_suffix(*quote))
+            .unwrap_or(value);
+        env.insert(key.to_string(), value.to_string());
+    }
+    Ok(env)
+}
+
+/// `<workspace>/logs/<suite>`, or `DIR/<suite>` with `--log-dir`.
+fn logs_root(options: &RunOptions, suite: &SuiteSpec, workspace: &Path) -> Result<PathBuf> {
+    match &options.log_dir {
+        Some(dir) => Ok(std::path::absolute(dir)
+            .with_context(|| format!("failed to resolve log directory {}", dir.display()))?
+            .join(suite.dir_name())),
+        None => Ok(workspace.join("logs").join(suite.dir_name())),
+    }
+}
+
+fn run_id_now(clock: &dyn Clock) -> String {
+    clock.now().format("%Y%m%d-%H%M%S").to_string()
+}
+
+/// A run id no earlier run under `logs_root` used; back-to-back runs (as
+/// with `--watch`) wait for the clock to reach the next second.
+fn fresh_run_id(logs_root: &Path, clock: &dyn Clock) -> String {
+    loop {
+        let run_id = run_id_now(clock);
+        if !logs_root.join(&run_id).exists() {
+            return run_id;
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+}
+
+fn list_cases(suite: &SuiteSpec, workspace: &Path, options: &RunOptions) -> Result<RunStatus> {
+    let Selection {
+        manifest,
+        script_env,
+        cases,
+        ..
+    } = select_cases(suite, workspace, options)?;
+    if options.explain {
+        explain_cases(suite, workspace, options, &manifest, &script_env, &cases, false)?;
+        return Ok(RunStatus::Passed);
+    }
+    for case in &cases {
+        let timeout = case.timeout_secs.unwrap_or(manifest.default_timeout_secs);
+        println!(
+            "{:<28} {} {}",
+            case.name.bright_white(),
+            command_line(Path::new(&case.path), &case.args),
+            format!("(timeout {timeout}s)").dimmed()
+        );
+    }
+    Ok(RunStatus::Passed)
+}
+
+/// One case as `--explain` reports it.
+#[derive(Debug, Serialize)]
+struct ExplainedCase {
+    name: String,
+    program: PathBuf,
+    argv: Vec<String>,
+    cwd: PathBuf,
+    base_timeout_secs: u64,
+    timeout_multiplier: f64,
+    timeout_secs: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    boot_timeout_secs: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    deadline_secs: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stdin_file: Option<PathBuf>,
+    env: BTreeMap<String, String>,
+}
 
-    let logs_root = workspace.join("logs").join(suite.dir_name());
-    fs::create_dir_all(&logs_root)?;
-    let timestamp = Local::now().format("%Y%m%d-%H%M%S").to_string();
+/// Prints what every selected case would execute. Run-specific values (run
+/// id, log and artifact paths) are those of a run started right now.
+fn explain_cases(
+    suite: &SuiteSpec,
+    workspace: &Path,
+    options: &RunOptions,
+    manifest: &Manifest,
+    script_env: &ScriptEnv,
+    cases: &[TestCase],
+    enforce_timeouts: bool,
+) -> Result<()> {
+    let planner = dry_planner(suite, workspace, options, manifest, script_env, enforce_timeouts)?;
+    let timeout_multiplier = planner.timeout_multiplier;
+    let explained = cases
+        .iter()
+        .map(|case| {
+            let plan = planner.plan(case, &planner.case_paths(case));
+            let argv = plan.argv();
+            let env = plan.env_map();
+            ExplainedCase {
+                name: case.name.clone(),
+                deadline_secs: plan.deadline_secs(),
+                program: plan.program,
+                argv,
+                cwd: plan.cwd,
+                base_timeout_secs: plan.base_timeout_secs,
+                timeout_multiplier,
+                timeout_secs: plan.timeout_secs,
+                boot_timeout_secs: plan.boot_timeout_secs,
+                stdin_file: case.stdin_file.as_ref().map(|file| workspace.join(file)),
+                env,
+            }
+        })
+        .collect::<Vec<_>>();
+    println!("{}", serde_json::to_string_pretty(&explained)?);
+    Ok(())
+}
+
+/// A planner for a run that starts now, without creating its directories.
+fn dry_planner<'a>(
+    suite: &SuiteSpec,
+    workspace: &'a Path,
+    options: &RunOptions,
+    manifest: &'a Manifest,
+    script_env: &ScriptEnv,
+    enforce_timeouts: bool,
+) -> Result<CasePlanner<'a>> {
+    let run_id = run_id_now(&clock::SystemClock);
+    let run_dir = logs_root(options, suite, workspace)?.join(&run_id);
+    Ok(CasePlanner {
+        workspace,
+        artifacts_root: artifacts_root(options, suite, &run_dir, &run_id)?,
+        run_dir,
+        run_id,
+        default_timeout_secs: manifest.default_timeout_secs,
+        timeout_multiplier: options.timeout_multiplier.max(MIN_TIMEOUT_MULTIPLIER),
+        runner: CaseRunner::from_manifest(manifest, workspace),
+        tracer: options
+            .trace_syscalls
+            .then(|| SyscallTracer::resolve(manifest, script_env, workspace).ok())
+            .flatten(),
+        enforce_timeouts,
+        suite_env: suite_env(options, manifest)?,
+        script_env: script_env.clone(),
+    })
+}
+
+/// Name of the made-up case `dump-env` plans.
+const DUMP_ENV_CASE: &str = "example-case";
+
+/// `dump-env`: plans a made-up case of the suite through the same planner as
+/// real runs and prints the harness variables it would get, with the values
+/// a run starting now would use.
+fn dump_env(suite: &SuiteSpec, workspace: &Path, options: &RunOptions) -> Result<RunStatus> {
+    let manifest_path = resolve_manifest_path(workspace, suite, options.manifest.as_deref())?;
+    let manifest = load_manifest(&manifest_path)?;
+    let script_env = ScriptEnv::resolve(&manifest, workspace)?;
+    let planner = dry_planner(suite, workspace, options, &manifest, &script_env, false)?;
+    let case: TestCase = serde_json::from_value(serde_json::json!({
+        "name": DUMP_ENV_CASE,
+        "path": "true",
+    }))
+    .context("failed to build the dump-env case")?;
+    let plan = planner.plan(&case, &planner.case_paths(&case));
+    println!("# Variables set for every case of {}, shown for a case named {DUMP_ENV_CASE}", suite.display_name());
+    for (key, value) in plan.env_map() {
+        if key.starts_with("STARRY_") {
+            println!("export {key}={}", shell_quote(&value));
+        }
+    }
+    Ok(RunStatus::Passed)
+}
+
+/// Single-quotes `value` for POSIX shells.
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// A command as one line for logs and listings, quoting only the words a
+/// shell would split or expand, so paths with spaces read unambiguously.
+fn command_line(program: &Path, args: &[String]) -> String {
+    std::iter::once(shell_word(&program.to_string_lossy()))
+        .chain(args.iter().map(|arg| shell_word(arg)))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// `word` as is when a shell would take it literally, single-quoted otherwise.
+fn shell_word(word: &str) -> String {
+    let plain = !word.is_empty()
+        && word
+            .chars()
+            .all(|c| c.is_alphanumeric() || "_-+=:,./@%".contains(c));
+    if plain { word.to_string() } else { shell_quote(word) }
+}
+
+fn run_suite(suite: &SuiteSpec, workspace: &Path, options: &RunOptions, clock: &dyn Clock) -> Result<RunStatus> {
+    let selection = select_cases(suite, workspace, options)?;
+    run_selection(suite, workspace, options, selection, clock)
+}
+
+/// Runs already selected cases; `exec` enters here with a one-case manifest.
+fn run_selection(
+    suite: &SuiteSpec,
+    workspace: &Path,
+    options: &RunOptions,
+    selection: Selection,
+    clock: &dyn Clock,
+) -> Result<RunStatus> {
+    let Selection {
+        manifest,
+        script_env,
+        cases,
+        shuffle,
+        adhoc,
+    } = selection;
+    if options.explain {
+        explain_cases(suite, workspace, options, &manifest, &script_env, &cases, adhoc)?;
+        return Ok(RunStatus::Passed);
+    }
+    if cases.is_empty() {
+        if let Some(base) = &options.only_changed {
+            println!("[only-changed] no cases affected by changes since {base}; nothing to run");
+        }
+        return Ok(RunStatus::Passed);
+    }
+    let sentinels = compile_sentinels(&manifest)?;
+    let classifiers = compile_classifiers(&manifest)?;
+    let normalize = checks::compile_normalize("suite", &manifest.normalize)?;
+    let suite_env = suite_env(options, &manifest)?;
+    for case in &cases {
+        case.validate()?;
+    }
+    check_required_tools(&manifest, &script_env, workspace)?;
+    procgroup::become_subreaper();
+    interrupt::install();
+
+    let logs_root = logs_root(options, suite, workspace)?;
+    fs::create_dir_all(&logs_root)
+        .with_context(|| format!("failed to create log directory {}", logs_root.display()))?;
+    let summary_path = logs_root.join("last_run.json");
+    // --since swaps the previous run for an older one, for timings too.
+    let (baseline_id, previous_run) = match options.since {
+        Some(since) => {
+            let (id, run) = history::select_baseline(&logs_root, since)?;
+            (id, Some(run))
+        }
+        None => ("last_run.json".to_string(), history::PreviousRun::load(&summary_path)),
+    };
+    let timestamp = fresh_run_id(&logs_root, clock);
     let run_dir = logs_root.join(&timestamp);
     fs::create_dir_all(&run_dir)?;
     let run_log_path = run_dir.join("suite.log");
     let case_logs_root = run_dir.join("cases");
     fs::create_dir_all(&case_logs_root)?;
-    let artifacts_root = run_dir.join("artifacts");
-    fs::create_dir_all(&artifacts_root)?;
-    let mut run_log = File::create(&run_log_path)?;
-    let start = Local::now();
+    let artifacts_root = artifacts_root(options, suite, &run_dir, &timestamp)?;
+    fs::create_dir_all(&artifacts_root)
+        .with_context(|| format!("failed to create artifact directory {}", artifacts_root.display()))?;
+    let mut run_log = logfile::LogFile::create(&run_log_path, options.timestamps)?;
+    let start = clock.now();
+    // Case offsets come from one monotonic clock so they stay consistent even
+    // if the wall clock jumps mid-run.
+    let suite_clock = Instant::now();
     let suite_label = manifest
         .name
         .clone()
@@ -206,89 +1893,354 @@ fn run_suite(suite: Suite, workspace: &Path) -> Result<()> {
             .as_deref()
             .unwrap_or("no description provided")
     );
-    writeln!(run_log, "{}", suite_header)?;
+    writeln!(run_log, "{suite_header}")?;
+
+    let timeout_multiplier = options.timeout_multiplier.max(MIN_TIMEOUT_MULTIPLIER);
+    if timeout_multiplier != options.timeout_multiplier {
+        let clamp_msg = format!(
+            "[suite] timeout multiplier {} is below the minimum, using {}",
+            options.timeout_multiplier, timeout_multiplier
+        );
+        writeln!(run_log, "{clamp_msg}")?;
+        println!("{}", clamp_msg.bright_yellow());
+    }
+    writeln!(run_log, "[suite] timeout multiplier: x{timeout_multiplier}")?;
+    let phased = cases.iter().any(|case| case.phase != 0);
+    if phased {
+        let mut phases = BTreeMap::new();
+        for case in &cases {
+            *phases.entry(case.phase).or_insert(0usize) += 1;
+        }
+        let phases = phases
+            .iter()
+            .map(|(phase, count)| format!("{phase} ({count} case(s))"))
+            .collect::<Vec<_>>();
+        writeln!(run_log, "[suite] phases: {}", phases.join(", "))?;
+    }
+    let shuffle_note = shuffle.as_ref().map(|info| {
+        let seed = match &info.seed_from {
+            Some(text) => format!("seed {} from `{text}`", info.seed),
+            None => format!("seed {}", info.seed),
+        };
+        match info.group_seed {
+            Some(group_seed) => format!("{} ({seed}, group seed {group_seed})", info.mode),
+            None => format!("{} ({seed})", info.mode),
+        }
+    });
+    if let Some(note) = &shuffle_note {
+        writeln!(run_log, "[suite] shuffled order: {note}")?;
+    }
 
+    let glyphs = options.glyphs();
     println!();
-    println!("{}", "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━".bright_blue());
-    println!("{}", format!("  {} Test Suite", suite_label).bright_white().bold());
-    println!("{}", "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━".bright_blue());
+    println!("{}", glyphs.rule().bright_blue());
+    println!("{}", format!("  {suite_label} Test Suite").bright_white().bold());
+    println!("{}", glyphs.rule().bright_blue());
     println!("  {}: {}", "Architecture".bright_cyan(), manifest.arch.as_deref().unwrap_or("unknown"));
     println!("  {}: {}", "Description".bright_cyan(), manifest.description.as_deref().unwrap_or("no description"));
+    if timeout_multiplier != 1.0 {
+        println!("  {}: x{}", "Timeout Multiplier".bright_cyan(), timeout_multiplier);
+    }
+    if let Some(note) = &shuffle_note {
+        println!("  {}: {}", "Shuffle".bright_cyan(), note);
+    }
     println!("  {}: {}{}", "Test Cases".bright_cyan(), cases.len(), if cases.len() != manifest.cases.len() { format!(" (filtered from {})", manifest.cases.len()) } else { String::new() });
-    println!("{}", "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━".bright_blue());
+    println!("{}", glyphs.rule().bright_blue());
     println!();
 
-    maybe_run_build(&manifest, suite, workspace, &mut run_log)?;
+    let status_server = match &options.serve {
+        Some(addr) => {
+            let case_names = cases.iter().map(|case| case.name.clone()).collect();
+            let server = serve::StatusServer::start(addr, &suite_label, start, case_names)?;
+            let msg = format!("[serve] live status at http://{}/status", server.addr());
+            writeln!(run_log, "{msg}")?;
+            println!("{}", msg.bright_cyan());
+            Some(server)
+        }
+        None => None,
+    };
+
+    let mut build_detail = None;
+    let build = if adhoc {
+        Ok(())
+    } else {
+        let detail = build_detail.insert(BuildDetail {
+            started_offset_ms: suite_clock.elapsed().as_millis(),
+            ..BuildDetail::default()
+        });
+        maybe_run_build(&manifest, &script_env, suite, workspace, &mut run_log, detail)
+    };
+    let setup_error = match build {
+        Ok(()) => None,
+        Err(err) if options.keep_going => {
+            let reason = format!("{err:#}");
+            let msg = format!("[suite] setup failed, skipping every case (--keep-going): {reason}");
+            writeln!(run_log, "{msg}")?;
+            println!("{}", msg.bright_red());
+            Some(reason)
+        }
+        Err(err) => return Err(err),
+    };
+
+    let tracer = if options.trace_syscalls {
+        match SyscallTracer::resolve(&manifest, &script_env, workspace) {
+            Ok(tracer) => {
+                writeln!(
+                    run_log,
+                    "[suite] tracing case syscalls with {} (--trace-syscalls)",
+                    tracer.program.display()
+                )?;
+                Some(tracer)
+            }
+            Err(reason) => {
+                let warn = format!("[trace-syscalls] {reason}");
+                writeln!(run_log, "{warn}")?;
+                println!("{}", warn.bright_yellow());
+                None
+            }
+        }
+    } else {
+        None
+    };
+    let ctx = RunContext {
+        planner: CasePlanner {
+            workspace,
+            run_dir: run_dir.clone(),
+            artifacts_root: artifacts_root.clone(),
+            run_id: timestamp.clone(),
+            default_timeout_secs: manifest.default_timeout_secs,
+            timeout_multiplier,
+            runner: CaseRunner::from_manifest(&manifest, workspace),
+            tracer,
+            enforce_timeouts: adhoc,
+            suite_env,
+            script_env,
+        },
+        strict_orphans: options.strict_orphans,
+        merge_output: options.merge_output,
+        fail_on_stderr: options.fail_on_stderr,
+        proc_snapshot: options.proc_snapshot,
+        poll_interval: Duration::from_millis(options.poll_interval_ms),
+        timestamps: options.timestamps,
+        sentinels,
+        normalize,
+        default_warn_after_secs: manifest.default_warn_after_secs,
+        default_stall_timeout_secs: manifest.default_stall_timeout_secs,
+        skip_exit_code: manifest.skip_exit_code,
+        suite_log: RefCell::new(run_log.try_clone()?),
+        console_notes: Cell::new(0),
+        compact: options.compact,
+        print_failing_command: options.print_failing_command,
+        glyphs,
+    };
+    if let Some(runner) = &ctx.planner.runner {
+        writeln!(
+            run_log,
+            "[suite] wrapping cases with runner {} (boot timeout {}s)",
+            runner.program.display(),
+            runner.boot_timeout_secs
+        )?;
+    }
 
-    let mut case_details = Vec::new();
-    let mut passed = 0usize;
-    let mut failed = 0usize;
-    let mut soft_failed = 0usize;
+    let accumulator = results::ResultsAccumulator::new();
+    let mut bailed_after = None;
 
     for (idx, case) in cases.iter().enumerate() {
-        let case_slug = sanitize_case_name(&case.name);
-        let case_log_path = case_logs_root.join(format!("{case_slug}.log"));
-        let case_artifact_dir = artifacts_root.join(&case_slug);
+        if interrupt::requested() {
+            break;
+        }
+        if phased && (idx == 0 || cases[idx - 1].phase != case.phase) {
+            writeln!(run_log, "[suite] phase {}", case.phase)?;
+        }
+        if let Some(server) = &status_server {
+            accumulator.with_cases(|cases| server.update(cases, Some(&case.name)));
+        }
+        let paths = ctx.planner.case_paths(case);
+        let case_log_path = paths.log_path.clone();
+        let case_artifact_dir = paths.artifact_dir.clone();
         fs::create_dir_all(&case_artifact_dir)?;
 
-        println!();
-        let case_header = format!("┌─ Test Case [{}/{}]: {}", idx + 1, cases.len(), case.name);
-        println!("{}", case_header.bright_yellow());
+        let failed = accumulator.failed();
+        let skip = match (&setup_error, options.bail_after) {
+            (Some(reason), _) => Some((format!("setup failed: {reason}"), "setup failed")),
+            (None, Some(limit)) if failed >= limit => {
+                Some((format!("--bail-after {limit}: {failed} cases already failed"), "bailed"))
+            }
+            _ => None,
+        };
+        if let Some((reason, short)) = skip {
+            if setup_error.is_none() && bailed_after.is_none() {
+                bailed_after = Some(failed);
+                let msg = format!("[suite] {failed} cases failed, skipping the rest (--bail-after)");
+                writeln!(run_log, "{msg}")?;
+                println!("{}", msg.bright_red());
+            }
+            fs::write(&case_log_path, format!("[case] {}\n[case] skipped: {reason}\n", case.name))?;
+            writeln!(run_log, "[case] {} skipped: {short}", case.name)?;
+            println!("{} {}", glyphs.status(&CaseStatus::Skipped).dimmed(), format!("{} ({short})", case.name).dimmed());
+            let detail = CaseDetail {
+                name: case.name.clone(),
+                id: case.id.clone(),
+                description: case.description.clone(),
+                group: case.group.clone(),
+                status: CaseStatus::Skipped.as_str().to_string(),
+                started_offset_ms: suite_clock.elapsed().as_millis(),
+                duration_ms: 0,
+                exit_code: None,
+                allow_failure: case.allow_failure,
+                log_path: rel_path(&case_log_path, workspace),
+                orphaned_pids: Vec::new(),
+                core_dumps: Vec::new(),
+                log_tail: None,
+                failing_command: None,
+                attempts: 1,
+                attempt_logs: Vec::new(),
+                failure_class: None,
+                stderr_line: None,
+                skip_reason: Some(reason.clone()),
+            };
+            write_case_result(&ctx, case, &paths, &detail)?;
+            accumulator.record(detail);
+            continue;
+        }
+
+        let ramp_ms = if idx > 0 { options.start_delay_ms } else { 0 };
+        let delay_ms = ramp_ms + case.delay_before_ms;
+        if delay_ms > 0 {
+            writeln!(
+                run_log,
+                "[suite] waiting {delay_ms}ms before {} (start delay {ramp_ms}ms + delay_before_ms {}ms)",
+                case.name, case.delay_before_ms
+            )?;
+            if !interrupt::sleep(Duration::from_millis(delay_ms)) {
+                break;
+            }
+        }
 
+        let case_header = format!("{}Test Case [{}/{}]: {}", glyphs.box_top(), idx + 1, cases.len(), case.name);
         let desc_line_count = if case.description.is_some() { 1 } else { 0 };
-        if let Some(desc) = &case.description {
-            println!("{} {}", "│ ".bright_yellow(), desc.bright_white());
+        if !options.compact {
+            println!();
+            println!("{}", case_header.bright_yellow());
+            if let Some(desc) = &case.description {
+                println!("{} {}", glyphs.box_side().bright_yellow(), desc.bright_white());
+            }
+            println!("{} {}: {}", glyphs.box_side().bright_yellow(), "Log".bright_cyan(), rel_path(&case_log_path, workspace).display().to_string().dimmed());
+            println!("{} {}", glyphs.box_bottom().bright_yellow(), "Running...".bright_yellow());
         }
-        println!("{} {}: {}", "│ ".bright_yellow(), "Log".bright_cyan(), rel_path(&case_log_path, workspace).display().to_string().dimmed());
-        println!("{} {}", "└─".bright_yellow(), "Running...".bright_yellow());
 
         let case_start_msg = format!(
             "[case] starting {} -> {}",
             case.name,
             rel_path(&case_log_path, workspace).display()
         );
-        writeln!(run_log, "{}", case_start_msg)?;
+        writeln!(run_log, "{case_start_msg}")?;
         if let Some(desc) = &case.description {
-            writeln!(run_log, "        {}", desc)?;
+            writeln!(run_log, "        {desc}")?;
         }
 
-        let outcome = run_case(
-            case,
-            workspace,
-            &case_log_path,
-            manifest.default_timeout_secs,
-            &run_dir,
-            &case_artifact_dir,
-            &timestamp,
-            &case_slug,
-        )?;
+        let started_offset_ms = suite_clock.elapsed().as_millis();
+        ctx.console_notes.set(0);
+        let retries = case.retries.unwrap_or(manifest.default_retries);
+        let mut attempts = 1;
+        let mut attempt_logs = Vec::new();
+        let outcome = loop {
+            let outcome = run_case(case, &ctx, &paths)?;
+            let decision = match retry_decision(case, retries, &outcome, attempts) {
+                Some(_) if interrupt::requested() => None,
+                decision => decision,
+            };
+            let trigger = match decision {
+                Some(Ok(trigger)) => trigger,
+                Some(Err(reason)) => {
+                    writeln!(run_log, "[case] {} attempt {attempts} failed; not retrying: {reason}", case.name)?;
+                    break outcome;
+                }
+                None => break outcome,
+            };
+            let kept = paths.log_path.with_file_name(format!("{}.attempt{attempts}.log", paths.slug));
+            fs::rename(&paths.log_path, &kept)?;
+            let note = format!(
+                "attempt {attempts}/{} failed ({trigger}); retrying",
+                retries + 1
+            );
+            writeln!(run_log, "[case] {} {note}; log kept at {}", case.name, rel_path(&kept, workspace).display())?;
+            ctx.console_note(&format!("{} {note}", case.name));
+            attempt_logs.push(kept);
+            attempts += 1;
+        };
 
         let status_str = outcome.status.as_str();
         let case_finish_msg = format!(
-            "[case] {} finished in {} ms (exit {:?})",
-            case.name, outcome.duration_ms, outcome.exit_code
+            "[case] {} finished in {} ms (exit {:?}, timeout budget {}s)",
+            case.name, outcome.duration_ms, outcome.exit_code, outcome.timeout_secs
         );
-        writeln!(run_log, "{}", case_finish_msg)?;
+        writeln!(run_log, "{case_finish_msg}")?;
+        if !outcome.orphaned_pids.is_empty() {
+            let orphan_msg = format!(
+                "[case] {} left orphaned processes behind: {:?}",
+                case.name, outcome.orphaned_pids
+            );
+            writeln!(run_log, "{orphan_msg}")?;
+            println!("{}", orphan_msg.bright_yellow());
+        }
+        for core in &outcome.core_dumps {
+            writeln!(
+                run_log,
+                "[case] {} dumped core: {}",
+                case.name,
+                rel_path(core, workspace).display()
+            )?;
+        }
 
         let duration_sec = outcome.duration_ms as f64 / 1000.0;
         let (status_colored, box_color): (colored::ColoredString, fn(colored::ColoredString) -> colored::ColoredString) = match outcome.status {
-            CaseStatus::Passed => (format!("✓ PASSED").bright_green(), |s| s.bright_green()),
-            CaseStatus::Failed => (format!("✗ FAILED").bright_red(), |s| s.bright_red()),
-            CaseStatus::SoftFailed => (format!("⚠ SOFT FAIL").bright_yellow(), |s| s.bright_yellow()),
+            CaseStatus::Passed => (glyphs.status(&outcome.status).bright_green(), |s| s.bright_green()),
+            CaseStatus::Failed => (glyphs.status(&outcome.status).bright_red(), |s| s.bright_red()),
+            CaseStatus::SoftFailed => (glyphs.status(&outcome.status).bright_yellow(), |s| s.bright_yellow()),
+            CaseStatus::FlakyFailed => (glyphs.status(&outcome.status).yellow(), |s| s.yellow()),
+            CaseStatus::Interrupted => (glyphs.status(&outcome.status).bright_magenta(), |s| s.bright_magenta()),
+            CaseStatus::Skipped => (glyphs.status(&outcome.status).dimmed(), |s| s.dimmed()),
         };
 
         // Check if stdout is a TTY (interactive terminal)
-        let is_tty = std::io::stdout().is_terminal();
-        let failed_lines = outcome
+        let is_tty = tee::stdout_is_terminal();
+        let mut failed_lines = outcome
             .failed_details
             .as_ref()
             .map(|details| format_failed_subtest_lines(details))
             .unwrap_or_default();
+        if let Some(line) = &outcome.stderr_line {
+            failed_lines.push(format!("stderr: {line}"));
+        }
+        if let Some(secs) = outcome.stalled_after_secs {
+            failed_lines.push(format!("stalled: no output for {secs}s"));
+        }
 
-        if is_tty {
+        if options.compact {
+            let detail = match (&outcome.status, outcome.signal, outcome.exit_code) {
+                (CaseStatus::Passed, _, _) => String::new(),
+                (_, Some(signal), _) => format!(" signal={signal}"),
+                (_, None, Some(code)) => format!(" exit={code}"),
+                (_, None, None) => String::new(),
+            };
+            let line = format!("{} {duration_sec:.2}s{detail}", case.name);
+            let (symbol, line) = match &outcome.status {
+                CaseStatus::Passed => (glyphs.mark(&outcome.status).bright_green(), line.normal()),
+                CaseStatus::Failed => (glyphs.mark(&outcome.status).bright_red(), line.bright_red()),
+                CaseStatus::SoftFailed => (glyphs.mark(&outcome.status).bright_yellow(), line.bright_yellow()),
+                CaseStatus::FlakyFailed => (glyphs.mark(&outcome.status).yellow(), line.yellow()),
+                CaseStatus::Interrupted => (glyphs.mark(&outcome.status).bright_magenta(), line.bright_magenta()),
+                CaseStatus::Skipped => (glyphs.mark(&outcome.status).dimmed(), line.dimmed()),
+            };
+            println!("{symbol} {line}");
+            for line in &failed_lines {
+                println!("    {}", line.bright_red());
+            }
+        } else if is_tty {
             // Move cursor up to the start of the test case box and redraw with result color
             // Number of lines to move up: 1 (└─ line) + 1 (Log line) + desc_line_count + 1 (header)
-            let lines_to_move = 3 + desc_line_count;
+            let lines_to_move = 3 + desc_line_count + ctx.console_notes.get();
             for _ in 0..lines_to_move {
                 print!("\x1b[1A\x1b[2K");  // Move up and clear line
             }
@@ -296,36 +2248,36 @@ fn run_suite(suite: Suite, workspace: &Path) -> Result<()> {
             // Redraw the entire box with the result color
             println!("{}", box_color(case_header.into()));
             if let Some(desc) = &case.description {
-                println!("{} {}", box_color("│ ".into()), desc.bright_white());
+                println!("{} {}", box_color(glyphs.box_side().into()), desc.bright_white());
             }
-            println!("{} {}: {}", box_color("│ ".into()), "Log".bright_cyan(), rel_path(&case_log_path, workspace).display().to_string().dimmed());
+            println!("{} {}: {}", box_color(glyphs.box_side().into()), "Log".bright_cyan(), rel_path(&case_log_path, workspace).display().to_string().dimmed());
             if failed_lines.is_empty() {
                 println!(
                     "{} {} {}",
-                    box_color("└─".into()),
+                    box_color(glyphs.box_bottom().into()),
                     status_colored,
-                    format!("(completed in {:.2}s)", duration_sec).dimmed()
+                    format!("(completed in {duration_sec:.2}s)").dimmed()
                 );
             } else {
                 println!(
                     "{} {} {}",
-                    box_color("│ ".into()),
+                    box_color(glyphs.box_side().into()),
                     status_colored,
-                    format!("(completed in {:.2}s)", duration_sec).dimmed()
+                    format!("(completed in {duration_sec:.2}s)").dimmed()
                 );
                 for (idx, line) in failed_lines.iter().enumerate() {
                     let indent = if idx == 0 { "  " } else { "    " };
-                    let formatted = format!("{}{}", indent, line);
+                    let formatted = format!("{indent}{line}");
                     if idx + 1 == failed_lines.len() {
                         println!(
                             "{} {}",
-                            box_color("└─".into()),
+                            box_color(glyphs.box_bottom().into()),
                             formatted.bright_red()
                         );
                     } else {
                         println!(
                             "{} {}",
-                            box_color("│ ".into()),
+                            box_color(glyphs.box_side().into()),
                             formatted.bright_red()
                         );
                     }
@@ -333,43 +2285,148 @@ fn run_suite(suite: Suite, workspace: &Path) -> Result<()> {
             }
         } else {
             // Non-TTY (like GitHub Actions): just print the result line
-            println!("{} {}", status_colored, format!("(completed in {:.2}s)", duration_sec).dimmed());
+            println!("{} {}", status_colored, format!("(completed in {duration_sec:.2}s)").dimmed());
             for line in &failed_lines {
                 println!("{}", line.bright_red());
             }
         }
-
-        match outcome.status {
-            CaseStatus::Passed => passed += 1,
-            CaseStatus::Failed => failed += 1,
-            CaseStatus::SoftFailed => soft_failed += 1,
+        for diff in &outcome.check_diffs {
+            diff.print();
         }
 
-        case_details.push(CaseDetail {
+        let log_tail = match (options.log_tail, &outcome.status) {
+            (Some(lines), CaseStatus::Failed | CaseStatus::SoftFailed | CaseStatus::FlakyFailed) => {
+                Some(log_tail(&outcome.log_path, lines))
+            }
+            _ => None,
+        };
+        let failure_class = match outcome.status {
+            // A stall is its own class unless a classifier says more.
+            CaseStatus::Failed | CaseStatus::SoftFailed | CaseStatus::FlakyFailed => classify_failure(&outcome.log_path, &classifiers)
+                .or_else(|| outcome.stalled_after_secs.map(|_| STALLED_CLASS.to_string())),
+            _ => None,
+        };
+        let detail = CaseDetail {
             name: case.name.clone(),
+            id: case.id.clone(),
+            description: case.description.clone(),
+            group: case.group.clone(),
             status: status_str.to_string(),
+            started_offset_ms,
             duration_ms: outcome.duration_ms,
             exit_code: outcome.exit_code,
             allow_failure: case.allow_failure,
             log_path: rel_path(&outcome.log_path, workspace),
-        });
+            orphaned_pids: outcome.orphaned_pids,
+            core_dumps: outcome
+                .core_dumps
+                .iter()
+                .map(|path| rel_path(path, workspace))
+                .collect(),
+            log_tail,
+            failing_command: outcome.failing_command,
+            attempts,
+            attempt_logs: attempt_logs.iter().map(|path| rel_path(path, workspace)).collect(),
+            failure_class,
+            stderr_line: outcome.stderr_line,
+            skip_reason: outcome.skip_reason,
+        };
+        write_case_result(&ctx, case, &paths, &detail)?;
+        accumulator.record(detail);
     }
 
-    let end = Local::now();
+    let results = accumulator.into_summary();
+    let pass_rate = options
+        .min_pass_rate
+        .map(|min_rate| PassRate::new(&results, min_rate, options.pass_rate_allows_soft_fails));
+    let failed = results.failed;
+    let case_details = results.cases;
+    if let Some(server) = &status_server {
+        server.finish(&case_details);
+    }
+    let interrupted = interrupt::requested();
+    if interrupted {
+        writeln!(
+            run_log,
+            "[suite] interrupted after {} of {} cases",
+            case_details.len(),
+            cases.len()
+        )?;
+    }
+    // Only for `exec`: a suite run may share the process with other runs (the
+    // unit tests run suites on parallel threads), and waiting on any child
+    // could steal one of their statuses. An `exec` run is the whole process
+    // and no case is running any more.
+    if adhoc {
+        let strays = procgroup::reap_strays();
+        if !strays.is_empty() {
+            writeln!(run_log, "[suite] reaped {} exited stray processes: {strays:?}", strays.len())?;
+        }
+    }
+    let end = clock.now();
+    let max_duration = options.max_duration_secs.map(|limit_secs| {
+        let duration_secs = end.signed_duration_since(start).num_milliseconds() as f64 / 1000.0;
+        MaxDuration {
+            limit_secs,
+            duration_secs,
+            exceeded: duration_secs > limit_secs as f64,
+            warn_only: options.max_duration_warn_only,
+        }
+    });
+    if let Some(limit) = &max_duration
+        && limit.exceeded
+    {
+        writeln!(
+            run_log,
+            "[suite] run took {:.2}s, over --max-duration-secs {}",
+            limit.duration_secs, limit.limit_secs
+        )?;
+    }
     let error_log_path = run_dir.join("error.log");
     let mut error_log = None;
-    if failed > 0 {
-        let message = format!(
-            "{} cases failed. See {} for details.",
-            failed,
-            rel_path(&run_log_path, workspace).display()
-        );
+    if failed > 0 || setup_error.is_some() {
+        let message = match &setup_error {
+            Some(reason) => format!(
+                "Setup failed ({reason}); all {} cases skipped. See {} for details.",
+                cases.len(),
+                rel_path(&run_log_path, workspace).display()
+            ),
+            None => format!(
+                "{} cases failed. See {} for details.",
+                failed,
+                rel_path(&run_log_path, workspace).display()
+            ),
+        };
         fs::write(&error_log_path, message)?;
         error_log = Some(rel_path(&error_log_path, workspace));
     } else if error_log_path.exists() {
         let _ = fs::remove_file(&error_log_path);
     }
 
+    let groups = group_summaries(&case_details);
+    let failure_classes = if classifiers.is_empty() && case_details.iter().all(|case| case.failure_class.is_none()) {
+        BTreeMap::new()
+    } else {
+        failure_histogram(&case_details)
+    };
+    let mut slowest_cases = Vec::new();
+    let mut slowed_down_cases = Vec::new();
+    if let Some(top) = options.top_slow {
+        let timings = history::timings(&case_details, previous_run.as_ref());
+        slowed_down_cases = timings
+            .iter()
+            .filter(|t| t.change_pct.is_some_and(|pct| pct >= options.slowdown_threshold))
+            .cloned()
+            .collect();
+        slowest_cases = timings.into_iter().take(top).collect();
+    }
+    let comparison = match &previous_run {
+        Some(baseline) if options.compare || options.notify_on_change.is_some() => {
+            Some(history::compare(&case_details, baseline, &baseline_id))
+        }
+        _ => None,
+    };
+
     let summary = RunSummary {
         suite: suite_label,
         action: "run".into(),
@@ -378,156 +2435,1132 @@ fn run_suite(suite: Suite, workspace: &Path) -> Result<()> {
         started_at: start,
         finished_at: end,
         total: cases.len(),
-        passed,
+        passed: results.passed,
         failed,
-        soft_failed,
+        soft_failed: results.soft_failed,
+        flaky_failed: results.flaky_failed,
+        skipped: results.skipped,
+        setup_error,
+        interrupted,
+        bailed_after,
         log_file: rel_path(&run_log_path, workspace),
         error_log,
         case_logs_root: rel_path(&case_logs_root, workspace),
         artifacts_root: rel_path(&artifacts_root, workspace),
         cases: case_details,
+        slowest_cases,
+        slowed_down_cases,
+        groups,
+        failure_classes,
+        shuffle,
+        comparison: if options.compare { comparison.clone() } else { None },
+        build: build_detail,
+        max_duration,
+        pass_rate,
     };
 
-    let summary_path = logs_root.join("last_run.json");
-    fs::write(&summary_path, serde_json::to_string_pretty(&summary)?)?;
+    let summary_json = serde_json::to_string_pretty(&summary)?;
+    fs::write(&summary_path, &summary_json)?;
+    fs::write(run_dir.join(history::RUN_SUMMARY_FILE), &summary_json)?;
+    // Stops the --serve thread now that the final status is on disk.
+    drop(status_server);
 
     let total_duration = end.signed_duration_since(start);
     let duration_secs = total_duration.num_milliseconds() as f64 / 1000.0;
+    if let Some(path) = &options.metrics_file {
+        write_metrics(path, suite.dir_name(), &summary, duration_secs)
+            .with_context(|| format!("failed to write metrics to {}", path.display()))?;
+    }
+    if let Some(path) = &options.trace {
+        trace::write(path, &summary).with_context(|| format!("failed to write trace to {}", path.display()))?;
+    }
+    let notice = match &options.notify_on_change {
+        Some(path) => {
+            let note = if notice::write(path, &summary, comparison.as_ref())? {
+                let reason = if comparison.is_some() { "results changed" } else { "no earlier run" };
+                format!("[notify] {reason}; wrote {}", path.display())
+            } else {
+                format!("[notify] no pass/fail changes; {} not written", path.display())
+            };
+            Some(note)
+        }
+        None => None,
+    };
+
+    match options.format {
+        OutputFormat::Text => print_summary(&summary, options, duration_secs),
+        OutputFormat::Json => println!("{summary_json}"),
+    }
+    if let Some(note) = &notice {
+        println!("{}", note.dimmed());
+    }
+    let skips_rejected = options.no_skips_allowed && summary.skipped > 0;
+    let clean = failed == 0
+        && summary.soft_failed == 0
+        && summary.flaky_failed == 0
+        && summary.setup_error.is_none()
+        && !interrupted
+        && !skips_rejected;
+    let discard = match options.keep {
+        KeepRun::Always => false,
+        KeepRun::OnFailure => clean,
+        KeepRun::Never => true,
+    };
+    if discard {
+        fs::remove_dir_all(&run_dir)
+            .with_context(|| format!("failed to remove run directory {}", run_dir.display()))?;
+        if artifacts_root.exists() {
+            fs::remove_dir_all(&artifacts_root)
+                .with_context(|| format!("failed to remove artifact directory {}", artifacts_root.display()))?;
+        }
+        let note = format!("[suite] removed {} (--keep {})", rel_path(&run_dir, workspace).display(), keep_label(options.keep));
+        println!("{}", note.dimmed());
+    }
+
+    if interrupted {
+        eprintln!(
+            "Error: {} interrupted; partial summary in {}",
+            suite.display_name(),
+            rel_path(&summary_path, workspace).display()
+        );
+        return Ok(RunStatus::Interrupted);
+    }
+    if let Some(reason) = &summary.setup_error {
+        // --keep-going only keeps the report coming; a broken setup never passes.
+        eprintln!("Error: {} setup failed: {reason}", suite.display_name());
+        return Ok(RunStatus::HarnessError);
+    }
+    let mut status = match &summary.pass_rate {
+        Some(rate) if rate.met => {
+            let note = format!(
+                "[pass-rate] {:.1}% of {} cases passed, meeting --min-pass-rate {}",
+                rate.rate, rate.counted, rate.min_rate
+            );
+            println!("{}", note.dimmed());
+            RunStatus::Passed
+        }
+        Some(rate) => {
+            eprintln!(
+                "Error: {} pass rate {:.1}% ({} of {} cases) is below --min-pass-rate {}. Consult {}",
+                suite.display_name(),
+                rate.rate,
+                rate.passing,
+                rate.counted,
+                rate.min_rate,
+                summary.log_file.display()
+            );
+            RunStatus::TestsFailed
+        }
+        None => {
+            if failed > 0 {
+                eprintln!(
+                    "Error: {} failed. Consult {}",
+                    suite.display_name(),
+                    summary.log_file.display()
+                );
+            }
+            RunStatus::from_failures(failed)
+        }
+    };
+    if skips_rejected {
+        eprintln!(
+            "Error: {} skipped {} of {} cases (--no-skips-allowed)",
+            suite.display_name(),
+            summary.skipped,
+            summary.total
+        );
+        status = RunStatus::TestsFailed;
+    }
+    if status == RunStatus::Passed
+        && let Some(limit) = &summary.max_duration
+        && limit.exceeded
+        && !limit.warn_only
+    {
+        eprintln!(
+            "Error: {} passed but took {:.2}s, over --max-duration-secs {}",
+            suite.display_name(),
+            limit.duration_secs,
+            limit.limit_secs
+        );
+        return Ok(RunStatus::TooSlow);
+    }
+    Ok(status)
+}
+
+fn keep_label(keep: KeepRun) -> String {
+    keep.to_possible_value().map_or_else(String::new, |value| value.get_name().to_string())
+}
+
+fn print_summary(summary: &RunSummary, options: &RunOptions, duration_secs: f64) {
+    let glyphs = options.glyphs();
+    println!();
+    println!("{}", glyphs.rule().bright_blue());
+    println!("{}", "  Test Suite Summary".bright_white().bold());
+    println!("{}", glyphs.rule().bright_blue());
+    println!("  {}: {} tests", "Total".bright_cyan(), summary.total);
+    println!("  {}: {}", "Passed".bright_green(), summary.passed.to_string().bright_green().bold());
+    if summary.failed > 0 {
+        println!("  {}: {}", "Failed".bright_red(), summary.failed.to_string().bright_red().bold());
+    }
+    if summary.soft_failed > 0 {
+        println!("  {}: {}", "Soft Fail".bright_yellow(), summary.soft_failed.to_string().bright_yellow().bold());
+    }
+    if summary.flaky_failed > 0 {
+        println!("  {}: {}", "Flaky Fail".yellow(), summary.flaky_failed.to_string().yellow().bold());
+    }
+    if summary.skipped > 0 {
+        println!("  {}: {}", "Skipped".dimmed(), summary.skipped.to_string().bold());
+    }
+    if summary.interrupted {
+        println!("  {}: after {} of {} cases", "Interrupted".bright_magenta(), summary.cases.len(), summary.total);
+    }
+    if let Some(failures) = summary.bailed_after {
+        println!("  {}: after {failures} failures (--bail-after)", "Bailed".bright_red());
+    }
+    println!("  {}: {:.2}s", "Duration".bright_cyan(), duration_secs);
+    if let Some(build) = &summary.build
+        && !build.skipped
+    {
+        println!(
+            "  {}: {:.2}s ({})",
+            "Build".bright_cyan(),
+            build.duration_ms as f64 / 1000.0,
+            build.script
+        );
+    }
+    if let Some(limit) = &summary.max_duration
+        && limit.exceeded
+    {
+        println!(
+            "  {}: {}",
+            "Too Slow".bright_yellow(),
+            format!("over --max-duration-secs {}", limit.limit_secs).bright_yellow()
+        );
+    }
+    if let Some(rate) = &summary.pass_rate {
+        let line = format!("{:.1}% ({}/{}), minimum {}%", rate.rate, rate.passing, rate.counted, rate.min_rate);
+        let line = if rate.met { line.bright_green() } else { line.bright_red() };
+        println!("  {}: {}", "Pass Rate".bright_cyan(), line);
+    }
+    println!("  {}: {}", "Log".bright_cyan(), summary.log_file.display().to_string().dimmed());
+    if !summary.groups.is_empty() {
+        print_group_report(&summary.groups);
+    }
+    if !summary.failure_classes.is_empty() {
+        println!("  {}:", "Failure Categories".bright_cyan());
+        for (label, count) in &summary.failure_classes {
+            println!("    {:<16} {}", label, count.to_string().bright_red());
+        }
+    }
+    let skipped = summary
+        .cases
+        .iter()
+        .filter(|case| case.status == CaseStatus::Skipped.as_str())
+        .collect::<Vec<_>>();
+    if !skipped.is_empty() {
+        println!("  {}:", "Skipped Cases".bright_cyan());
+        for case in skipped {
+            println!(
+                "    {}: {}",
+                case.name,
+                case.skip_reason.as_deref().unwrap_or("no reason recorded").dimmed()
+            );
+        }
+    }
+    for case in &summary.cases {
+        if let Some(command) = &case.failing_command {
+            println!("  {} {}", format!("{} ({})", case.name, case.status).bright_red(), "reproduce with:".dimmed());
+            for line in command.lines() {
+                println!("    {line}");
+            }
+        }
+    }
+    for case in &summary.cases {
+        if let Some(tail) = &case.log_tail {
+            println!("  {} {}", format!("{} ({})", case.name, case.status).bright_red(), "log tail:".dimmed());
+            for line in tail {
+                println!("    {}", line.dimmed());
+            }
+        }
+    }
+    if options.top_slow.is_some() {
+        print_timing_report(summary, options.slowdown_threshold);
+    }
+    if options.compare {
+        print_comparison(summary.comparison.as_ref());
+    }
+    println!("{}", glyphs.rule().bright_blue());
+    println!();
+}
+
+/// Longest log line kept in a tail, in characters.
+const LOG_TAIL_LINE_CHARS: usize = 200;
+/// Cap on a whole tail, in bytes; the oldest lines are dropped first.
+const LOG_TAIL_MAX_BYTES: usize = 8 * 1024;
+
+fn log_tail(path: &Path, lines: usize) -> Vec<String> {
+    let Ok(content) = fs::read(path) else {
+        return Vec::new();
+    };
+    let content = String::from_utf8_lossy(&content);
+    let all = content.lines().collect::<Vec<_>>();
+    let mut tail = all[all.len().saturating_sub(lines)..]
+        .iter()
+        .map(|line| match line.char_indices().nth(LOG_TAIL_LINE_CHARS) {
+            Some((cut, _)) => format!("{}…", &line[..cut]),
+            None => line.to_string(),
+        })
+        .collect::<Vec<_>>();
+    let mut total: usize = tail.iter().map(String::len).sum();
+    while total > LOG_TAIL_MAX_BYTES && !tail.is_empty() {
+        total -= tail.remove(0).len();
+    }
+    tail
+}
+
+fn print_group_report(groups: &[GroupSummary]) {
+    println!("  {}:", "Groups".bright_cyan());
+    for group in groups {
+        let mut counts = format!("{}/{} passed", group.passed, group.total);
+        if group.failed > 0 {
+            counts.push_str(&format!(", {} failed", group.failed));
+        }
+        if group.soft_failed > 0 {
+            counts.push_str(&format!(", {} soft", group.soft_failed));
+        }
+        if group.flaky_failed > 0 {
+            counts.push_str(&format!(", {} flaky", group.flaky_failed));
+        }
+        let counts = if group.failed > 0 {
+            counts.bright_red()
+        } else {
+            counts.bright_green()
+        };
+        println!(
+            "    {:<16} {} {}",
+            group.name,
+            counts,
+            format!("({:.2}s)", group.duration_ms as f64 / 1000.0).dimmed()
+        );
+    }
+}
 
-    println!();
-    println!("{}", "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━".bright_blue());
-    println!("{}", "  Test Suite Summary".bright_white().bold());
-    println!("{}", "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━".bright_blue());
-    println!("  {}: {} tests", "Total".bright_cyan(), summary.total);
-    println!("  {}: {}", "Passed".bright_green(), passed.to_string().bright_green().bold());
-    if failed > 0 {
-        println!("  {}: {}", "Failed".bright_red(), failed.to_string().bright_red().bold());
+fn print_timing_report(summary: &RunSummary, threshold_pct: f64) {
+    println!("  {}:", "Slowest Cases".bright_cyan());
+    for (idx, timing) in summary.slowest_cases.iter().enumerate() {
+        let change = timing
+            .change_pct
+            .map(|pct| format!(" ({pct:+.0}% vs previous)"))
+            .unwrap_or_default();
+        println!(
+            "    {}. {} {}{}",
+            idx + 1,
+            timing.name,
+            format!("{:.2}s", timing.duration_ms as f64 / 1000.0).bright_white(),
+            change.dimmed()
+        );
     }
-    if soft_failed > 0 {
-        println!("  {}: {}", "Soft Fail".bright_yellow(), soft_failed.to_string().bright_yellow().bold());
+    if !summary.slowed_down_cases.is_empty() {
+        println!(
+            "  {}:",
+            format!("Slower Than Previous Run (>= {threshold_pct:.0}%)").bright_yellow()
+        );
+        for timing in &summary.slowed_down_cases {
+            println!(
+                "    - {} {:.2}s -> {:.2}s ({:+.0}%)",
+                timing.name,
+                timing.previous_duration_ms.unwrap_or_default() as f64 / 1000.0,
+                timing.duration_ms as f64 / 1000.0,
+                timing.change_pct.unwrap_or_default()
+            );
+        }
     }
-    println!("  {}: {:.2}s", "Duration".bright_cyan(), duration_secs);
-    println!("  {}: {}", "Log".bright_cyan(), summary.log_file.display().to_string().dimmed());
-    println!("{}", "━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━".bright_blue());
-    println!();
+}
 
-    if failed > 0 {
-        bail!(
-            "{} failed. Consult {}",
-            suite.display_name(),
-            summary.log_file.display()
-        );
+/// Writes Prometheus textfile metrics through a temporary file and a rename,
+/// so node_exporter never scrapes a half-written file.
+fn write_metrics(path: &Path, suite: &str, summary: &RunSummary, duration_secs: f64) -> Result<()> {
+    let label = suite.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n");
+    let metrics = [
+        ("starry_tests_total", "Cases selected for the run.", summary.total as f64),
+        ("starry_tests_passed", "Cases that passed.", summary.passed as f64),
+        ("starry_tests_failed", "Cases that failed.", summary.failed as f64),
+        ("starry_tests_soft_failed", "Failures tolerated by allow_failure.", summary.soft_failed as f64),
+        ("starry_tests_flaky_failed", "Failures of cases marked flaky.", summary.flaky_failed as f64),
+        ("starry_suite_duration_seconds", "Wall-clock duration of the run.", duration_secs),
+    ];
+    let mut text = String::new();
+    for (name, help, value) in metrics {
+        text.push_str(&format!("# HELP {name} {help}\n# TYPE {name} gauge\n{name}{{suite=\"{label}\"}} {value}\n"));
     }
 
+    let file_name = path
+        .file_name()
+        .with_context(|| format!("{} is not a file path", path.display()))?;
+    let mut tmp_name = OsString::from(".");
+    tmp_name.push(file_name);
+    tmp_name.push(".tmp");
+    let tmp = path.with_file_name(tmp_name);
+    fs::write(&tmp, text)?;
+    fs::rename(&tmp, path)?;
     Ok(())
 }
 
-fn run_case(
-    case: &TestCase,
-    workspace: &Path,
-    log_path: &Path,
-    default_timeout: u64,
-    run_dir: &Path,
-    case_artifact_dir: &Path,
-    run_id: &str,
-    case_slug: &str,
-) -> Result<CaseOutcome> {
-    let script_path = workspace.join(&case.path);
-    if !script_path.exists() {
+fn print_comparison(comparison: Option<&history::Comparison>) {
+    let Some(comparison) = comparison else {
+        println!("  {}: no earlier run to compare against", "Compare".bright_cyan());
+        return;
+    };
+    println!("  {}: against {}", "Compare".bright_cyan(), comparison.baseline);
+    if comparison.regressed.is_empty() && comparison.fixed.is_empty() {
+        println!("    {}", "no status changes".dimmed());
+    }
+    for change in &comparison.regressed {
+        let line = format!("{} {} -> {}", change.name, change.before, change.after);
+        println!("    {} {}", "regressed:".bright_red(), line);
+    }
+    for change in &comparison.fixed {
+        let line = format!("{} {} -> {}", change.name, change.before, change.after);
+        println!("    {} {}", "fixed:".bright_green(), line);
+    }
+    if !comparison.added.is_empty() {
+        println!("    {} {}", "new cases:".dimmed(), comparison.added.join(", "));
+    }
+    if !comparison.removed.is_empty() {
+        println!("    {} {}", "not run:".dimmed(), comparison.removed.join(", "));
+    }
+}
+
+fn run_case(case: &TestCase, ctx: &RunContext, paths: &CasePaths) -> Result<CaseOutcome> {
+    let workspace = ctx.planner.workspace;
+    let log_path = paths.log_path.as_path();
+    let case_artifact_dir = paths.artifact_dir.as_path();
+    let plan = ctx.planner.plan(case, paths);
+    // Under a runner the case path names a binary inside the guest image.
+    if ctx.planner.runner.is_none() && !plan.program.exists() {
         bail!(
             "test case {} missing script {}",
             case.name,
-            script_path.display()
+            plan.program.display()
         );
     }
 
-    let mut log_file = File::create(log_path)?;
+    // The case sees these through STARRY_CASE_*; make an unwritable one a
+    // clear setup error instead of an opaque failure halfway through the case.
+    fs::create_dir_all(case_artifact_dir)?;
+    let case_log_dir = log_path.parent().unwrap_or_else(|| Path::new("."));
+    ensure_writable(case_log_dir)
+        .with_context(|| format!("test case {}: log directory is not writable", case.name))?;
+    ensure_writable(case_artifact_dir)
+        .with_context(|| format!("test case {}: artifact directory is not writable", case.name))?;
+
+    let mut log_file = logfile::LogFile::create(log_path, ctx.timestamps)?;
     writeln!(log_file, "[case] {}", case.name)?;
+    let timeout_secs = plan.timeout_secs;
+
+    let mut supervision = exec::Supervision::default();
     writeln!(
         log_file,
-        "[case] command: {} {}",
-        script_path.display(),
-        case.args.join(" ")
+        "[case] command: {}",
+        command_line(&plan.program, &plan.args)
+    )?;
+    if case.run_in_artifact_dir {
+        writeln!(log_file, "[case] cwd: {} (run_in_artifact_dir)", plan.cwd.display())?;
+    }
+    if let Some(tracer) = &ctx.planner.tracer {
+        writeln!(log_file, "[case] syscall trace: {}", tracer.output(case_artifact_dir).display())?;
+    }
+    if let (Some(boot_secs), Some(deadline_secs)) = (plan.boot_timeout_secs, plan.deadline_secs()) {
+        writeln!(
+            log_file,
+            "[case] runner deadline: {deadline_secs}s (boot {boot_secs}s + case {timeout_secs}s)"
+        )?;
+        supervision.deadline = Some(Duration::from_secs(deadline_secs));
+        supervision.stop_on_sentinel = true;
+    } else if let Some(deadline_secs) = plan.deadline_secs() {
+        writeln!(log_file, "[case] deadline: {deadline_secs}s, enforced by the harness")?;
+        supervision.deadline = Some(Duration::from_secs(deadline_secs));
+    }
+    writeln!(
+        log_file,
+        "[case] timeout budget: {timeout_secs}s (base {}s x{})",
+        plan.base_timeout_secs, ctx.planner.timeout_multiplier
     )?;
-    let timeout_secs = case.timeout_secs.unwrap_or(default_timeout);
-    writeln!(log_file, "[case] timeout budget: {}s", timeout_secs)?;
 
-    let mut command = Command::new(&script_path);
-    command.current_dir(workspace);
-    if !case.args.is_empty() {
-        command.args(&case.args);
+    if let Some(script) = &case.before_script {
+        let started = Instant::now();
+        if !run_case_hook(ctx, &plan, "before_script", script, &mut log_file)? {
+            writeln!(log_file, "[case] skipping the case command")?;
+            if let Some(script) = &case.after_script {
+                run_case_hook(ctx, &plan, "after_script", script, &mut log_file)?;
+            }
+            let status = if interrupt::requested() {
+                CaseStatus::Interrupted
+            } else {
+                failure_status(case, &mut log_file)?
+            };
+            return Ok(CaseOutcome {
+                status,
+                duration_ms: started.elapsed().as_millis(),
+                exit_code: None,
+                timeout_secs,
+                log_path: log_path.to_path_buf(),
+                failed_details: None,
+                orphaned_pids: Vec::new(),
+                core_dumps: Vec::new(),
+                signal: None,
+                check_diffs: Vec::new(),
+                stderr_line: None,
+                skip_reason: None,
+                stalled_after_secs: None,
+                failing_command: None,
+            });
+        }
     }
-    fs::create_dir_all(case_artifact_dir)?;
-    let case_log_dir = log_path.parent().unwrap_or_else(|| Path::new("."));
-    command.env("STARRY_WORKSPACE_ROOT", workspace);
-    command.env("STARRY_RUN_ID", run_id);
-    command.env("STARRY_RUN_DIR", run_dir);
-    command.env("STARRY_CASE_NAME", &case.name);
-    command.env("STARRY_CASE_SLUG", case_slug);
-    command.env("STARRY_CASE_LOG_PATH", log_path);
-    command.env("STARRY_CASE_LOG_DIR", case_log_dir);
-    command.env("STARRY_CASE_ARTIFACT_DIR", case_artifact_dir);
-    command.env("STARRY_CASE_TIMEOUT_SECS", timeout_secs.to_string());
-
-    let start = Instant::now();
-    let output = command
-        .output()
+
+    let mut command = plan.command();
+    coredump::allow_core_dumps(&mut command);
+
+    let binary = case.output_encoding == OutputEncoding::Binary;
+    // A runner's verdict only ever comes from the console sentinel.
+    if !binary || ctx.planner.runner.is_some() {
+        supervision.sentinels = Some(&ctx.sentinels);
+    }
+    supervision.poll_interval = ctx.poll_interval;
+    supervision.proc_snapshot = ctx.proc_snapshot;
+    supervision.merge_output = ctx.merge_output || case.merge_output;
+    if supervision.merge_output {
+        writeln!(log_file, "[case] stderr merged into stdout")?;
+    }
+    let stdin_path = case.stdin_file.as_ref().map(|file| workspace.join(file));
+    if let Some(path) = &stdin_path {
+        if !path.is_file() {
+            bail!(
+                "test case {} stdin_file {} does not exist",
+                case.name,
+                path.display()
+            );
+        }
+        writeln!(log_file, "[case] stdin: {}", path.display())?;
+        supervision.stdin = Some(path);
+    }
+    let warn_after_secs = case
+        .warn_after_secs
+        .or(ctx.default_warn_after_secs)
+        .map(|secs| scaled_timeout_secs(secs, ctx.planner.timeout_multiplier));
+    let notify_slow = |after: Duration| {
+        let note = format!("[case] {} still running after {}s", case.name, after.as_secs());
+        let _ = writeln!(ctx.suite_log.borrow_mut(), "{note}");
+        ctx.console_note(&note);
+    };
+    if let Some(secs) = warn_after_secs {
+        supervision.soft_deadline = Some(Duration::from_secs(secs));
+        supervision.on_soft_deadline = Some(&notify_slow);
+    }
+    let stall_timeout_secs = case
+        .stall_timeout_secs
+        .or(ctx.default_stall_timeout_secs)
+        .map(|secs| scaled_timeout_secs(secs, ctx.planner.timeout_multiplier));
+    if let Some(secs) = stall_timeout_secs {
+        writeln!(log_file, "[case] stall timeout: {secs}s without output")?;
+        supervision.stall_timeout = Some(Duration::from_secs(secs));
+    }
+    let spawned_at = SystemTime::now();
+    let execution = exec::supervise(&mut command, &supervision)
         .with_context(|| format!("failed to run {}", case.name))?;
-    let duration = start.elapsed().as_millis();
-    let failed_details = extract_failed_subtests(&output.stdout);
+    let duration = execution.elapsed.as_millis();
+    let failed_details = if binary { None } else { extract_failed_subtests(&execution.stdout) };
+
+    let streams = [
+        ("stdout", &execution.stdout, &execution.stdout_stamps),
+        ("stderr", &execution.stderr, &execution.stderr_stamps),
+    ];
+    for (stream, bytes, stamps) in streams {
+        match case.output_encoding {
+            OutputEncoding::Utf8 => log_file.write_captured(bytes, stamps)?,
+            OutputEncoding::Lossy => log_file.write_captured_lossy(bytes, stamps)?,
+            OutputEncoding::Binary if bytes.is_empty() => {}
+            OutputEncoding::Binary => {
+                writeln!(log_file, "[case] {stream}: {} bytes of binary output follow", bytes.len())?;
+                log_file.write_raw(bytes)?;
+            }
+        }
+    }
+    if case.output_encoding == OutputEncoding::Utf8 {
+        for (stream, bytes, _) in streams {
+            if let Err(err) = std::str::from_utf8(bytes) {
+                writeln!(
+                    log_file,
+                    "[case] warning: {stream} is not valid UTF-8 after byte {}; \
+                     set output_encoding = \"lossy\" or \"binary\"",
+                    err.valid_up_to()
+                )?;
+            }
+        }
+    }
+
+    if ctx.proc_snapshot {
+        save_proc_snapshot(execution.proc_snapshot.as_ref(), case_artifact_dir, &mut log_file)?;
+    }
+
+    let orphaned_pids = execution.orphaned_pids;
+    if !orphaned_pids.is_empty() {
+        writeln!(
+            log_file,
+            "[case] warning: processes still running after exit: {orphaned_pids:?}; killed them"
+        )?;
+    }
+    let orphan_failure = ctx.strict_orphans && !orphaned_pids.is_empty();
+    if orphan_failure {
+        writeln!(log_file, "[case] --strict-orphans: treating leaked processes as a failure")?;
+    }
+    if execution.soft_deadline_exceeded {
+        writeln!(
+            log_file,
+            "[case] warning: ran past the {}s soft threshold",
+            warn_after_secs.unwrap_or_default()
+        )?;
+    }
+    if execution.timed_out {
+        let deadline = if plan.boot_timeout_secs.is_some() { "runner deadline" } else { "deadline" };
+        writeln!(log_file, "[case] killed after exceeding the {deadline}")?;
+    }
+    if execution.stalled {
+        writeln!(
+            log_file,
+            "[case] stalled: killed after {}s without output",
+            stall_timeout_secs.unwrap_or_default()
+        )?;
+    }
+    if execution.interrupted {
+        writeln!(log_file, "[case] killed because the run was interrupted")?;
+    }
+    let mut core_dumps = Vec::new();
+    if !execution.status.success() {
+        core_dumps = coredump::collect(&plan.cwd, spawned_at, case_artifact_dir)
+            .with_context(|| format!("failed to collect core dumps for {}", case.name))?;
+        for core in &core_dumps {
+            writeln!(log_file, "[case] core dump saved to {}", core.display())?;
+        }
+    }
+
+    if let Some(hit) = &execution.sentinel {
+        writeln!(log_file, "[case] sentinel: {}", hit.line)?;
+    }
+    let succeeded = if ctx.planner.runner.is_some() {
+        // Emulators exit however they like; only the console sentinel counts.
+        if execution.sentinel.is_none() {
+            writeln!(log_file, "[case] no PASS/FAIL sentinel seen on the console")?;
+        }
+        execution
+            .sentinel
+            .as_ref()
+            .is_some_and(|hit| hit.kind == exec::Sentinel::Pass)
+    } else {
+        // Failure wins: a FAIL sentinel fails a zero exit, and a PASS sentinel
+        // never rescues a non-zero exit.
+        let exited_ok = if let Some(expected) = case.expected_signal {
+            match execution.status.signal() {
+                Some(signal) if signal == expected && !execution.timed_out && !execution.stalled => {
+                    writeln!(log_file, "[case] terminated by expected signal {signal}")?;
+                    true
+                }
+                _ => {
+                    writeln!(
+                        log_file,
+                        "[case] expected termination by signal {expected}, got {}",
+                        execution.status
+                    )?;
+                    false
+                }
+            }
+        } else if case.allowed_exit_codes.is_empty() {
+            execution.status.success()
+        } else {
+            let code = execution.status.code();
+            let accepted = code.is_some_and(|code| case.allowed_exit_codes.contains(&code));
+            if accepted && !execution.status.success() {
+                writeln!(
+                    log_file,
+                    "[case] exit {} accepted by allowed_exit_codes {:?}{}",
+                    code.unwrap_or_default(),
+                    case.allowed_exit_codes,
+                    reason_suffix(case)
+                )?;
+            } else if !accepted {
+                writeln!(
+                    log_file,
+                    "[case] exit {code:?} not in allowed_exit_codes {:?}",
+                    case.allowed_exit_codes
+                )?;
+            }
+            accepted
+        };
+        match execution.sentinel.as_ref().map(|hit| hit.kind) {
+            Some(exec::Sentinel::Fail) if exited_ok => {
+                writeln!(
+                    log_file,
+                    "[case] warning: exit code 0 disagrees with FAIL sentinel; marking failed"
+                )?;
+                false
+            }
+            Some(exec::Sentinel::Pass) if !exited_ok => {
+                writeln!(
+                    log_file,
+                    "[case] warning: PASS sentinel disagrees with exit {:?}; marking failed",
+                    execution.status.code()
+                )?;
+                false
+            }
+            _ => exited_ok,
+        }
+    };
+    let mut check_diffs = Vec::new();
+    let succeeded = if case.checks.is_empty() {
+        succeeded
+    } else {
+        let mut compiled = checks::compile(&case.name, &case.checks)?;
+        if binary {
+            let dropped = checks::drop_output_assertions(&mut compiled);
+            if dropped > 0 {
+                writeln!(
+                    log_file,
+                    "[case] output_encoding = binary: skipping the output assertions of {dropped} check(s)"
+                )?;
+            }
+        }
+        let mut rules = checks::compile_normalize(&format!("case {}", case.name), &case.normalize)?;
+        rules.splice(0..0, ctx.normalize.iter().cloned());
+        let mut stdout = String::from_utf8_lossy(&execution.stdout);
+        if !rules.is_empty() {
+            writeln!(log_file, "[case] checks see stdout after {} normalize rule(s)", rules.len())?;
+            stdout = checks::normalize(&rules, &stdout).into();
+        }
+        // Merged output arrives on stdout only; let stderr checks see it too.
+        let stderr = if supervision.merge_output {
+            stdout.clone()
+        } else {
+            String::from_utf8_lossy(&execution.stderr)
+        };
+        let failures = checks::evaluate(&compiled, &stdout, &stderr, execution.status.code());
+        for failure in &failures {
+            writeln!(log_file, "[case] {}", failure.message)?;
+            if let Some(diff) = &failure.diff {
+                for line in &diff.lines {
+                    writeln!(log_file, "[case]   {line}")?;
+                }
+            }
+        }
+        check_diffs.extend(failures.iter().filter_map(|failure| failure.diff.clone()));
+        if failures.is_empty() {
+            writeln!(log_file, "[case] all {} checks passed", compiled.len())?;
+        }
+        succeeded && failures.is_empty()
+    };
+    let mut stderr_line = None;
+    let succeeded = if !(ctx.fail_on_stderr || case.fail_on_stderr) {
+        succeeded
+    } else if supervision.merge_output {
+        writeln!(log_file, "[case] fail_on_stderr ignored: stderr is merged into stdout")?;
+        succeeded
+    } else {
+        match String::from_utf8_lossy(&execution.stderr).lines().find(|line| !line.trim().is_empty()) {
+            Some(first) => {
+                writeln!(log_file, "[case] fail_on_stderr: case wrote to stderr: {first}")?;
+                stderr_line = Some(first.to_string());
+                false
+            }
+            None => succeeded,
+        }
+    };
 
-    log_file.write_all(&output.stdout)?;
-    log_file.write_all(&output.stderr)?;
+    if let Some(script) = &case.after_script {
+        run_case_hook(ctx, &plan, "after_script", script, &mut log_file)?;
+    }
 
-    let status = if output.status.success() {
+    // Emulators exit however they like, so under a runner the code means nothing.
+    let skip_reason = match (ctx.skip_exit_code, execution.status.code()) {
+        (Some(skip), Some(code))
+            if code == skip
+                && ctx.planner.runner.is_none()
+                && !execution.timed_out
+                && !case.allowed_exit_codes.contains(&code) =>
+        {
+            Some(format!("exited with skip_exit_code {code}"))
+        }
+        _ => None,
+    };
+    let status = if execution.interrupted {
+        CaseStatus::Interrupted
+    } else if let Some(reason) = &skip_reason {
+        writeln!(log_file, "[case] skipped: {reason}")?;
+        CaseStatus::Skipped
+    } else if succeeded && !orphan_failure {
         CaseStatus::Passed
-    } else if case.allow_failure {
-        CaseStatus::SoftFailed
     } else {
-        CaseStatus::Failed
+        failure_status(case, &mut log_file)?
+    };
+    let failing_command = match status {
+        CaseStatus::Failed | CaseStatus::SoftFailed | CaseStatus::FlakyFailed if ctx.print_failing_command => {
+            let command = plan.failing_command(stdin_path.as_deref());
+            writeln!(log_file, "[case] reproduce with:")?;
+            for line in command.lines() {
+                writeln!(log_file, "{line}")?;
+            }
+            Some(command)
+        }
+        _ => None,
     };
 
     Ok(CaseOutcome {
         status,
         duration_ms: duration,
-        exit_code: output.status.code(),
+        exit_code: execution.status.code(),
+        timeout_secs,
         log_path: log_path.to_path_buf(),
         failed_details,
+        orphaned_pids,
+        core_dumps,
+        signal: execution.status.signal(),
+        check_diffs,
+        stderr_line,
+        skip_reason,
+        stalled_after_secs: stall_timeout_secs.filter(|_| execution.stalled),
+        failing_command,
+    })
+}
+
+/// Writes the `--proc-snapshot` files of a case, or notes why there are none.
+fn save_proc_snapshot(
+    snapshot: Option<&(procgroup::ProcSnapshot, Duration)>,
+    artifact_dir: &Path,
+    log_file: &mut logfile::LogFile,
+) -> Result<()> {
+    let Some((snapshot, age)) = snapshot else {
+        writeln!(
+            log_file,
+            "[case] --proc-snapshot: no snapshot (no /proc, or the command ended before the first poll)"
+        )?;
+        return Ok(());
+    };
+    let status_path = artifact_dir.join("proc-status.txt");
+    fs::write(&status_path, &snapshot.status).with_context(|| format!("failed to write {}", status_path.display()))?;
+    if let Some(rollup) = &snapshot.smaps_rollup {
+        let rollup_path = artifact_dir.join("proc-smaps_rollup.txt");
+        fs::write(&rollup_path, rollup).with_context(|| format!("failed to write {}", rollup_path.display()))?;
+    }
+    writeln!(
+        log_file,
+        "[case] /proc snapshot from {}ms before the command ended saved to {}",
+        age.as_millis(),
+        artifact_dir.display()
+    )?;
+    Ok(())
+}
+
+/// How a failed attempt is reported, given `allow_failure` and `flaky`.
+fn failure_status(case: &TestCase, log_file: &mut logfile::LogFile) -> Result<CaseStatus> {
+    Ok(if case.allow_failure {
+        writeln!(log_file, "[case] failure tolerated by allow_failure{}", reason_suffix(case))?;
+        CaseStatus::SoftFailed
+    } else if case.flaky {
+        writeln!(log_file, "[case] failure reported as flaky_failed (case is marked flaky)")?;
+        CaseStatus::FlakyFailed
+    } else {
+        CaseStatus::Failed
+    })
+}
+
+/// Runs a case's `before_script` or `after_script` with the case command's
+/// environment and working directory, under the same timeout budget, and
+/// appends its output to the case log. Returns whether it succeeded.
+fn run_case_hook(
+    ctx: &RunContext,
+    plan: &CasePlan,
+    kind: &str,
+    script: &str,
+    log_file: &mut logfile::LogFile,
+) -> Result<bool> {
+    let path = ctx.planner.workspace.join(script);
+    if !path.exists() {
+        writeln!(log_file, "[case] {kind} {} does not exist", path.display())?;
+        return Ok(false);
+    }
+    writeln!(log_file, "[case] {kind}: {}", path.display())?;
+    let (program, args) = ctx.planner.script_env.launch(path, Vec::new());
+    let mut command = Command::new(program);
+    command.args(args).current_dir(&plan.cwd);
+    for (key, value) in &plan.env {
+        command.env(key, value);
+    }
+    let supervision = exec::Supervision {
+        deadline: Some(Duration::from_secs(plan.timeout_secs)),
+        merge_output: true,
+        poll_interval: ctx.poll_interval,
+        ..Default::default()
+    };
+    let execution = exec::supervise(&mut command, &supervision).with_context(|| format!("failed to run {kind} {script}"))?;
+    log_file.write_captured_lossy(&execution.stdout, &execution.stdout_stamps)?;
+    if !execution.orphaned_pids.is_empty() {
+        writeln!(
+            log_file,
+            "[case] warning: {kind} left processes running: {:?}; killed them",
+            execution.orphaned_pids
+        )?;
+    }
+    if execution.timed_out {
+        writeln!(log_file, "[case] {kind} killed after {}s", plan.timeout_secs)?;
+    } else if execution.interrupted {
+        writeln!(log_file, "[case] {kind} killed because the run was interrupted")?;
+    }
+    let succeeded = execution.status.success() && !execution.timed_out;
+    if !succeeded {
+        writeln!(log_file, "[case] {kind} failed: {}", execution.status)?;
+    }
+    Ok(succeeded)
+}
+
+/// Whether a finished attempt earns another run: `None` when retries do not
+/// apply (passed, or out of the `retries` extra attempts), otherwise the
+/// predicate that fired or why none did.
+fn retry_decision(case: &TestCase, retries: u32, outcome: &CaseOutcome, attempt: u32) -> Option<Result<String, String>> {
+    if attempt > retries
+        || !matches!(outcome.status, CaseStatus::Failed | CaseStatus::SoftFailed | CaseStatus::FlakyFailed)
+    {
+        return None;
+    }
+    if case.retry_on_exit_codes.is_empty() && case.retry_on_signals.is_empty() {
+        return Some(Ok("any failure is retried".into()));
+    }
+    if let Some(code) = outcome.exit_code
+        && case.retry_on_exit_codes.contains(&code)
+    {
+        return Some(Ok(format!("exit {code} is in retry_on_exit_codes")));
+    }
+    if let Some(signal) = outcome.signal
+        && case.retry_on_signals.contains(&signal)
+    {
+        return Some(Ok(format!("signal {signal} is in retry_on_signals")));
+    }
+    let outcome = match (outcome.exit_code, outcome.signal) {
+        (_, Some(signal)) => format!("signal {signal}"),
+        (Some(code), None) => format!("exit {code}"),
+        (None, None) => "no exit status".to_string(),
+    };
+    Some(Err(format!(
+        "{outcome} matches neither retry_on_exit_codes {:?} nor retry_on_signals {:?}",
+        case.retry_on_exit_codes, case.retry_on_signals
+    )))
+}
+
+fn is_single_attempt(attempts: &u32) -> bool {
+    *attempts == 1
+}
+
+fn single_attempt() -> u32 {
+    1
+}
+
+/// Creates and removes a probe file in `dir`.
+fn ensure_writable(dir: &Path) -> Result<()> {
+    let probe = dir.join(".starry-write-probe");
+    fs::File::create(&probe).with_context(|| format!("cannot create files in {}", dir.display()))?;
+    fs::remove_file(&probe).with_context(|| format!("cannot remove files in {}", dir.display()))
+}
+
+fn reason_suffix(case: &TestCase) -> String {
+    case.allow_reason
+        .as_ref()
+        .map(|reason| format!(" ({reason})"))
+        .unwrap_or_default()
+}
+
+fn compile_sentinels(manifest: &Manifest) -> Result<exec::Sentinels> {
+    Ok(exec::Sentinels {
+        pass: Regex::new(&manifest.success_pattern)
+            .with_context(|| format!("invalid success_pattern `{}`", manifest.success_pattern))?,
+        fail: Regex::new(&manifest.failure_pattern)
+            .with_context(|| format!("invalid failure_pattern `{}`", manifest.failure_pattern))?,
+    })
+}
+
+fn compile_classifiers(manifest: &Manifest) -> Result<Vec<(Regex, String)>> {
+    manifest
+        .failure_classifiers
+        .iter()
+        .map(|classifier| {
+            let regex = Regex::new(&classifier.pattern).with_context(|| {
+                format!(
+                    "invalid failure_classifiers pattern `{}` (label {})",
+                    classifier.pattern, classifier.label
+                )
+            })?;
+            Ok((regex, classifier.label.clone()))
+        })
+        .collect()
+}
+
+/// The label of the first classifier whose pattern occurs in the case log.
+fn classify_failure(log_path: &Path, classifiers: &[(Regex, String)]) -> Option<String> {
+    if classifiers.is_empty() {
+        return None;
+    }
+    let bytes = fs::read(log_path).ok()?;
+    let content = String::from_utf8_lossy(&bytes);
+    classifiers
+        .iter()
+        .find(|(regex, _)| regex.is_match(&content))
+        .map(|(_, label)| label.clone())
+}
+
+fn failure_histogram(cases: &[CaseDetail]) -> BTreeMap<String, usize> {
+    let mut histogram = BTreeMap::new();
+    for case in cases {
+        if ![CaseStatus::Failed, CaseStatus::SoftFailed, CaseStatus::FlakyFailed]
+            .iter()
+            .any(|status| case.status == status.as_str())
+        {
+            continue;
+        }
+        let label = case.failure_class.as_deref().unwrap_or("unclassified");
+        *histogram.entry(label.to_string()).or_insert(0) += 1;
+    }
+    histogram
+}
+
+/// Emits a JSON Schema for `suite.toml`, derived from the manifest types so it
+/// never drifts from what the harness actually accepts.
+fn print_manifest_schema() -> Result<()> {
+    let schema = schemars::schema_for!(Manifest);
+    println!("{}", serde_json::to_string_pretty(&schema)?);
+    Ok(())
+}
+
+/// Why a suite's `suite.toml` cannot be used, told apart so first-time setup
+/// problems get a pointed message and their own exit code.
+#[derive(Debug)]
+enum ManifestError {
+    NotFound { path: PathBuf },
+    Parse { path: PathBuf, message: String },
+    NoCases { suite: String, path: PathBuf },
+}
+
+impl ManifestError {
+    fn status(&self) -> RunStatus {
+        match self {
+            ManifestError::NotFound { .. } => RunStatus::ManifestNotFound,
+            ManifestError::Parse { .. } => RunStatus::ManifestInvalid,
+            ManifestError::NoCases { .. } => RunStatus::ManifestEmpty,
+        }
+    }
+}
+
+impl std::fmt::Display for ManifestError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ManifestError::NotFound { path } => write!(
+                f,
+                "manifest {} not found - create it with at least one [[cases]] entry, or point --manifest at an existing suite.toml",
+                path.display()
+            ),
+            ManifestError::Parse { path, message } => write!(
+                f,
+                "failed to parse manifest {}: {} (--print-schema lists the accepted fields)",
+                path.display(),
+                message.trim_end()
+            ),
+            ManifestError::NoCases { suite, path } => write!(
+                f,
+                "suite {suite} has no cases defined - add [[cases]] entries to {}",
+                path.display()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ManifestError {}
+
+fn load_manifest(path: &Path) -> Result<Manifest> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            return Err(ManifestError::NotFound { path: path.to_path_buf() }.into());
+        }
+        Err(err) => return Err(err).with_context(|| format!("failed to read manifest {}", path.display())),
+    };
+    toml::from_str(&content).map_err(|err| {
+        ManifestError::Parse {
+            path: path.to_path_buf(),
+            message: err.to_string(),
+        }
+        .into()
     })
 }
 
-fn load_manifest(workspace: &Path, suite: Suite) -> Result<Manifest> {
-    let path = manifest_path(workspace, suite);
-    let content = fs::read_to_string(&path)
-        .with_context(|| format!("failed to read manifest {}", path.display()))?;
-    toml::from_str(&content).with_context(|| format!("failed to parse manifest {}", path.display()))
+fn list_suites(workspace: &Path) {
+    for suite in Suite::value_variants() {
+        let Some(spec) = suite.builtin() else {
+            continue;
+        };
+        let name = suite
+            .to_possible_value()
+            .map(|value| value.get_name().to_string())
+            .unwrap_or_default();
+        let manifest = manifest_path(workspace, &spec);
+        let state = if manifest.is_file() {
+            "suite.toml present".green()
+        } else {
+            "suite.toml missing".dimmed()
+        };
+        println!(
+            "{:<14} tests/{:<14} {:<20} {}",
+            name.bright_white().bold(),
+            spec.dir_name(),
+            spec.display_name(),
+            state
+        );
+    }
 }
 
-fn manifest_path(workspace: &Path, suite: Suite) -> PathBuf {
+fn manifest_path(workspace: &Path, suite: &SuiteSpec) -> PathBuf {
     workspace
         .join("tests")
         .join(suite.dir_name())
         .join("suite.toml")
 }
 
+/// `--manifest` wins over the suite's conventional location; a relative
+/// override is taken from the current directory like any other CLI path.
+fn resolve_manifest_path(workspace: &Path, suite: &SuiteSpec, manifest: Option<&Path>) -> Result<PathBuf> {
+    match manifest {
+        Some(path) => std::path::absolute(path)
+            .with_context(|| format!("failed to resolve manifest {}", path.display())),
+        None => Ok(manifest_path(workspace, suite)),
+    }
+}
+
 fn maybe_run_build(
     manifest: &Manifest,
-    suite: Suite,
+    script_env: &ScriptEnv,
+    suite: &SuiteSpec,
     workspace: &Path,
-    log: &mut File,
+    log: &mut logfile::LogFile,
+    detail: &mut BuildDetail,
 ) -> Result<()> {
     let script = manifest
         .build_script
         .as_deref()
         .unwrap_or("scripts/build_stub.sh");
     let script_path = workspace.join(script);
+    detail.script = script.to_string();
     if !script_path.exists() {
+        detail.skipped = true;
         let skip_msg = format!(
             "[build] skipped build step because {} does not exist",
             script_path.display()
         );
-        writeln!(log, "{}", skip_msg)?;
-        println!("{}", skip_msg);
+        writeln!(log, "{skip_msg}")?;
+        println!("{skip_msg}");
         return Ok(());
     }
 
@@ -536,18 +3569,53 @@ fn maybe_run_build(
         script_path.display(),
         suite.display_name()
     );
-    writeln!(log, "{}", build_start_msg)?;
-    println!("{}", build_start_msg);
-    let output = Command::new(&script_path)
-        .arg(suite.dir_name())
-        .current_dir(workspace)
-        .output()
-        .with_context(|| format!("failed to run build script {}", script_path.display()))?;
-    log.write_all(&output.stdout)?;
-    log.write_all(&output.stderr)?;
-    print!("{}", String::from_utf8_lossy(&output.stdout));
-    eprint!("{}", String::from_utf8_lossy(&output.stderr));
-    Ok(())
+    writeln!(log, "{build_start_msg}")?;
+    println!("{build_start_msg}");
+    let attempts = manifest.build_retries + 1;
+    let mut delay_secs = manifest.build_retry_delay_secs;
+    let started = Instant::now();
+    for attempt in 1..=attempts {
+        detail.attempts = attempt;
+        if attempts > 1 {
+            let attempt_msg = format!("[build] attempt {attempt}/{attempts}");
+            writeln!(log, "{attempt_msg}")?;
+            println!("{attempt_msg}");
+        }
+        let (program, args) = script_env.launch(script_path.clone(), vec![suite.dir_name().to_string()]);
+        let mut command = Command::new(program);
+        command.args(args).current_dir(workspace);
+        if let Some(path) = &script_env.path {
+            command.env("PATH", path);
+        }
+        let output = command
+            .output()
+            .with_context(|| format!("failed to run build script {}", script_path.display()))?;
+        detail.duration_ms = started.elapsed().as_millis();
+        detail.exit_code = output.status.code();
+        log.write_all(&output.stdout)?;
+        log.write_all(&output.stderr)?;
+        print!("{}", String::from_utf8_lossy(&output.stdout));
+        eprint!("{}", String::from_utf8_lossy(&output.stderr));
+        if output.status.success() {
+            detail.success = true;
+            return Ok(());
+        }
+
+        let fail_msg = format!("[build] build script exited with {}", output.status);
+        writeln!(log, "{fail_msg}")?;
+        eprintln!("{fail_msg}");
+        if attempt < attempts {
+            let retry_msg = format!("[build] retrying in {delay_secs}s");
+            writeln!(log, "{retry_msg}")?;
+            println!("{retry_msg}");
+            thread::sleep(Duration::from_secs(delay_secs));
+            delay_secs = (delay_secs * 2).min(MAX_BUILD_RETRY_DELAY_SECS);
+        }
+    }
+    bail!(
+        "build script {} failed after {attempts} attempt(s)",
+        script_path.display()
+    )
 }
 
 
@@ -570,26 +3638,79 @@ fn filter_cases(cases: &[TestCase]) -> Result<Vec<TestCase>> {
 
     let filtered = cases
         .iter()
-        .cloned()
         .filter(|c| {
-            let slug = sanitize_case_name(&c.name);
-            selected.contains(&c.name) || selected.contains(&slug)
+            let slug = c.slug();
+            selected.contains(&c.name) || c.id.as_ref().is_some_and(|id| selected.contains(id)) || selected.contains(&slug)
         })
+        .cloned()
         .collect::<Vec<_>>();
     Ok(filtered)
 }
 
+fn select_changed_cases(
+    cases: Vec<TestCase>,
+    manifest: &Manifest,
+    manifest_file: &Path,
+    workspace: &Path,
+    base: &str,
+) -> Result<Vec<TestCase>> {
+    let changed_files = match changed::changed_files(workspace, base) {
+        Ok(files) => files,
+        Err(err) => {
+            let warn = format!("[only-changed] cannot diff against {base} ({err:#}); running all selected cases");
+            println!("{}", warn.bright_yellow());
+            return Ok(cases);
+        }
+    };
+    let mut suite_files = vec![rel_path(manifest_file, workspace).display().to_string()];
+    if let Some(script) = &manifest.build_script {
+        suite_files.push(script.clone());
+    }
+    match changed::select(&cases, &changed_files, &suite_files)? {
+        changed::ChangeSelection::Everything(trigger) => {
+            println!("[only-changed] {trigger} changed; running the full suite");
+            Ok(cases)
+        }
+        changed::ChangeSelection::Cases(selected) => {
+            println!(
+                "[only-changed] {} of {} cases affected by {} changed files since {base}",
+                selected.len(),
+                cases.len(),
+                changed_files.len()
+            );
+            Ok(selected)
+        }
+    }
+}
+
+/// Writes `cases/<slug>.json` next to the case log.
+fn write_case_result(ctx: &RunContext, case: &TestCase, paths: &CasePaths, detail: &CaseDetail) -> Result<()> {
+    let plan = ctx.planner.plan(case, paths);
+    let record = CaseResultFile {
+        detail,
+        command: plan.argv(),
+        env: plan.env_map(),
+        cwd: plan.cwd,
+        artifact_dir: rel_path(&paths.artifact_dir, ctx.planner.workspace),
+    };
+    let path = paths.log_path.with_extension("json");
+    fs::write(&path, serde_json::to_string_pretty(&record)?)
+        .with_context(|| format!("failed to write {}", path.display()))
+}
+
 fn rel_path(path: &Path, workspace: &Path) -> PathBuf {
     path.strip_prefix(workspace).unwrap_or(path).to_path_buf()
 }
 
+/// Lowercases letters and digits (any script, so `读写` keeps its name) and
+/// turns everything else into `-`.
 fn sanitize_case_name(name: &str) -> String {
     name.chars()
-        .map(|c| {
-            if c.is_ascii_alphanumeric() {
-                c.to_ascii_lowercase()
+        .flat_map(|c| {
+            if c.is_alphanumeric() {
+                c.to_lowercase().collect::<Vec<_>>()
             } else {
-                '-'
+                vec!['-']
             }
         })
         .collect::<String>()
@@ -633,10 +3754,10 @@ fn extract_failed_subtests(stdout: &[u8]) -> Option<Vec<FailedSubCaseDetail>> {
             continue;
         }
 
-        if let Some(name) = current_name.as_ref() {
-            if let Some(body) = sections.get_mut(name) {
-                body.push(line.to_string());
-            }
+        if let Some(name) = current_name.as_ref()
+            && let Some(body) = sections.get_mut(name)
+        {
+            body.push(line.to_string());
         }
     }
 
@@ -691,10 +3812,11 @@ fn summarize_failure_body(lines: &[String]) -> String {
             continue;
         }
 
-        if trimmed.starts_with("thread '") && trimmed.contains("panicked at") {
-            if let Some(idx) = trimmed.find("panicked at ") {
-                location = trimmed[idx + 12..].trim_end_matches(':').to_string();
-            }
+        if trimmed.starts_with("thread '")
+            && trimmed.contains("panicked at")
+            && let Some(idx) = trimmed.find("panicked at ")
+        {
+            location = trimmed[idx + 12..].trim_end_matches(':').to_string();
         }
 
         let is_meta = trimmed.starts_with("thread '")
@@ -712,8 +3834,207 @@ fn summarize_failure_body(lines: &[String]) -> String {
     }
 
     if !location.is_empty() {
-        format!("{} ({})", message, location)
+        format!("{message} ({location})")
     } else {
         message
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::fs::PermissionsExt;
+
+    use chrono::TimeZone;
+
+    use super::*;
+
+    #[test]
+    fn run_status_exit_codes_are_distinct() {
+        assert_eq!(RunStatus::Passed.code(), 0);
+        assert_eq!(RunStatus::TestsFailed.code(), 1);
+        assert_eq!(RunStatus::HarnessError.code(), 2);
+        assert_eq!(RunStatus::TooSlow.code(), 3);
+        assert_eq!(RunStatus::ManifestNotFound.code(), 4);
+        assert_eq!(RunStatus::ManifestInvalid.code(), 5);
+        assert_eq!(RunStatus::ManifestEmpty.code(), 6);
+    }
+
+    #[test]
+    fn run_status_reflects_hard_failures_only() {
+        // Soft failures are not counted in `failed`, so they still exit 0.
+        assert_eq!(RunStatus::from_failures(0), RunStatus::Passed);
+        assert_eq!(RunStatus::from_failures(1), RunStatus::TestsFailed);
+        assert_eq!(RunStatus::from_failures(7), RunStatus::TestsFailed);
+    }
+
+    #[derive(Parser)]
+    struct TestCli {
+        #[command(flatten)]
+        run: RunOptions,
+    }
+
+    /// Writes an executable shell script under the fixture workspace.
+    fn write_script(workspace: &Path, name: &str, body: &str) {
+        let path = workspace.join(name);
+        fs::write(&path, format!("#!/bin/sh\n{body}\n")).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    #[test]
+    fn last_run_json_round_trips_into_run_summary() {
+        let workspace = tempfile::tempdir().unwrap();
+        let workspace = fs::canonicalize(workspace.path()).unwrap();
+        let suite_dir = workspace.join("tests").join("fixture");
+        fs::create_dir_all(&suite_dir).unwrap();
+        write_script(&workspace, "pass.sh", "true");
+        write_script(&workspace, "fail.sh", "false");
+        fs::write(
+            suite_dir.join("suite.toml"),
+            r#"
+default_timeout_secs = 10
+
+[[cases]]
+name = "passes"
+path = "pass.sh"
+
+[[cases]]
+name = "fails"
+path = "fail.sh"
+
+[[cases]]
+name = "fails-softly"
+path = "fail.sh"
+allow_failure = true
+"#,
+        )
+        .unwrap();
+        let suite = SuiteSpec {
+            dir_name: "fixture".to_string(),
+            display_name: "Fixture".to_string(),
+        };
+        let options = TestCli::parse_from(["test"]).run;
+
+        let started = Local.with_ymd_and_hms(2025, 1, 7, 9, 30, 0).unwrap();
+        let status = run_suite(&suite, &workspace, &options, &clock::FixedClock(started)).unwrap();
+        assert_eq!(status, RunStatus::TestsFailed);
+        assert!(workspace.join("logs/fixture/20250107-093000/summary.json").is_file());
+
+        let json = fs::read_to_string(workspace.join("logs/fixture/last_run.json")).unwrap();
+        let summary: RunSummary = serde_json::from_str(&json).unwrap();
+        assert_eq!((summary.started_at, summary.finished_at), (started, started));
+        assert_eq!(summary.log_file, Path::new("logs/fixture/20250107-093000/suite.log"));
+        assert_eq!(summary.total, 3);
+        assert_eq!(summary.cases.len(), summary.total);
+        assert_eq!((summary.passed, summary.failed, summary.soft_failed), (1, 1, 1));
+        assert_eq!(
+            summary.passed + summary.failed + summary.soft_failed + summary.flaky_failed + summary.skipped,
+            summary.total
+        );
+        let statuses = summary.cases.iter().map(|case| case.status.as_str()).collect::<Vec<_>>();
+        assert_eq!(statuses, ["passed", "failed", "soft_failed"]);
+        assert!(summary.cases.iter().all(|case| case.attempts == 1));
+    }
+
+    #[test]
+    fn manifest_problems_get_their_own_exit_codes() {
+        let workspace = tempfile::tempdir().unwrap();
+        let workspace = fs::canonicalize(workspace.path()).unwrap();
+        let suite_dir = workspace.join("tests").join("fixture");
+        fs::create_dir_all(&suite_dir).unwrap();
+        let suite = SuiteSpec {
+            dir_name: "fixture".to_string(),
+            display_name: "Fixture".to_string(),
+        };
+        let options = TestCli::parse_from(["test"]).run;
+        let failure = |expected: RunStatus, hint: &str| {
+            let Err(err) = select_cases(&suite, &workspace, &options) else {
+                panic!("selecting cases should fail for {expected:?}");
+            };
+            let message = format!("{err:#}");
+            assert!(message.contains(hint), "{message}");
+            assert_eq!(report_error(err), expected);
+        };
+
+        failure(RunStatus::ManifestNotFound, "not found - create it");
+        fs::write(suite_dir.join("suite.toml"), "[[cases]]\nname = \"no-path\"\n").unwrap();
+        failure(RunStatus::ManifestInvalid, "missing field `path`");
+        fs::write(suite_dir.join("suite.toml"), "default_timeout_secs = 10\n").unwrap();
+        failure(RunStatus::ManifestEmpty, "suite Fixture has no cases defined");
+    }
+
+    #[test]
+    fn workspace_with_spaces_and_unicode_runs_cleanly() {
+        let root = tempfile::tempdir().unwrap();
+        let workspace = fs::canonicalize(root.path()).unwrap().join("weird dir").join("测试");
+        let suite_dir = workspace.join("tests").join("fixture");
+        fs::create_dir_all(&suite_dir).unwrap();
+        fs::create_dir_all(workspace.join("case scripts")).unwrap();
+        write_script(
+            &workspace,
+            "case scripts/读 写.sh",
+            r#"[ "$1" = "two words" ] && [ -f "$STARRY_CASE_LOG_PATH" ] && [ -d "$STARRY_CASE_ARTIFACT_DIR" ] && [ -d "$STARRY_WORKSPACE_ROOT/tests" ]"#,
+        );
+        fs::write(
+            suite_dir.join("suite.toml"),
+            r#"
+[[cases]]
+name = "管道 读写"
+path = "case scripts/读 写.sh"
+args = ["two words"]
+"#,
+        )
+        .unwrap();
+        let suite = SuiteSpec {
+            dir_name: "fixture".to_string(),
+            display_name: "Fixture".to_string(),
+        };
+        let options = TestCli::parse_from(["test"]).run;
+        let started = Local.with_ymd_and_hms(2025, 1, 7, 9, 30, 0).unwrap();
+        // A trailing slash must not leak into the relative paths.
+        let with_slash = PathBuf::from(format!("{}/", workspace.display()));
+        let status = run_suite(&suite, &with_slash, &options, &clock::FixedClock(started)).unwrap();
+        assert_eq!(status, RunStatus::Passed);
+
+        let json = fs::read_to_string(workspace.join("logs/fixture/last_run.json")).unwrap();
+        let summary: RunSummary = serde_json::from_str(&json).unwrap();
+        assert_eq!(summary.log_file, Path::new("logs/fixture/20250107-093000/suite.log"));
+        let log_path = Path::new("logs/fixture/20250107-093000/cases/管道-读写.log");
+        assert_eq!(summary.cases[0].log_path, log_path);
+        let log = fs::read_to_string(workspace.join(log_path)).unwrap();
+        let script = workspace.join("case scripts/读 写.sh");
+        assert!(
+            log.contains(&format!("[case] command: '{}' 'two words'", script.display())),
+            "{log}"
+        );
+    }
+
+    #[test]
+    fn compare_follows_a_renamed_case_by_id() {
+        let baseline: history::PreviousRun = serde_json::from_value(serde_json::json!({
+            "cases": [
+                { "name": "pipe basic", "id": "pipe-basic", "duration_ms": 10, "status": "passed" },
+                { "name": "old name", "duration_ms": 10, "status": "passed" },
+            ],
+        }))
+        .unwrap();
+        let cases = [
+            CaseDetail {
+                name: "pipe: basic read/write".to_string(),
+                id: Some("pipe-basic".to_string()),
+                status: "failed".to_string(),
+                ..CaseDetail::default()
+            },
+            CaseDetail {
+                name: "new name".to_string(),
+                status: "passed".to_string(),
+                ..CaseDetail::default()
+            },
+        ];
+        let comparison = history::compare(&cases, &baseline, "last_run.json");
+        assert_eq!(comparison.regressed.len(), 1);
+        assert_eq!(comparison.regressed[0].name, "pipe: basic read/write");
+        // Without an id a rename still reads as one case gone and another new.
+        assert_eq!(comparison.added, ["new name"]);
+        assert_eq!(comparison.removed, ["old name"]);
+    }
+}
@@ -0,0 +1,115 @@
+//! `--notify-on-change`: a Markdown report that only appears when results flip.
+//!
+//! Repeated runs of a stable suite would otherwise ping the team with "still
+//! all green" every time. The report is built on the `--compare` diff against
+//! the previous run (or `--since`) and written only when some case changed
+//! between passing and failing, or the set of cases changed. Without an
+//! earlier run to compare against it is always written.
+
+use std::{fmt::Write as _, fs, path::Path};
+
+use anyhow::{Context, Result};
+
+use crate::{RunSummary, history::Comparison};
+
+/// Writes the report to `path` when `comparison` shows a change, or when
+/// there is no baseline. Otherwise removes a report left by an earlier run, so
+/// "post the file if it exists" stays correct. Returns whether it was written.
+pub fn write(path: &Path, summary: &RunSummary, comparison: Option<&Comparison>) -> Result<bool> {
+    if let Some(comparison) = comparison
+        && !has_changes(comparison)
+    {
+        if path.exists() {
+            fs::remove_file(path)
+                .with_context(|| format!("failed to remove stale report {}", path.display()))?;
+        }
+        return Ok(false);
+    }
+    fs::write(path, render(summary, comparison))
+        .with_context(|| format!("failed to write change report {}", path.display()))?;
+    Ok(true)
+}
+
+fn has_changes(comparison: &Comparison) -> bool {
+    !comparison.regressed.is_empty()
+        || !comparison.fixed.is_empty()
+        || !comparison.added.is_empty()
+        || !comparison.removed.is_empty()
+}
+
+fn render(summary: &RunSummary, comparison: Option<&Comparison>) -> String {
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "## {}: {} of {} passed",
+        summary.suite, summary.passed, summary.total
+    );
+    let _ = writeln!(out);
+    let mut counts = vec![format!("{} failed", summary.failed)];
+    for (count, label) in [
+        (summary.soft_failed, "soft failed"),
+        (summary.flaky_failed, "flaky failed"),
+        (summary.skipped, "skipped"),
+    ] {
+        if count > 0 {
+            counts.push(format!("{count} {label}"));
+        }
+    }
+    let _ = writeln!(
+        out,
+        "{}. Log: `{}`",
+        counts.join(", "),
+        summary.log_file.display()
+    );
+    let _ = writeln!(out);
+
+    let Some(comparison) = comparison else {
+        let _ = writeln!(out, "No earlier run to compare against.");
+        let failed = summary
+            .cases
+            .iter()
+            .filter(|case| case.status == "failed")
+            .map(|case| case.name.as_str())
+            .collect::<Vec<_>>();
+        section(
+            &mut out,
+            "Failed",
+            failed.iter().map(|name| format!("`{name}`")),
+        );
+        return out;
+    };
+    let _ = writeln!(out, "Changes since `{}`:", comparison.baseline);
+    let transition = |change: &crate::history::StatusChange| {
+        format!("`{}`: {} → {}", change.name, change.before, change.after)
+    };
+    section(
+        &mut out,
+        "Regressed",
+        comparison.regressed.iter().map(transition),
+    );
+    section(&mut out, "Fixed", comparison.fixed.iter().map(transition));
+    section(
+        &mut out,
+        "New cases",
+        comparison.added.iter().map(|name| format!("`{name}`")),
+    );
+    section(
+        &mut out,
+        "Not run",
+        comparison.removed.iter().map(|name| format!("`{name}`")),
+    );
+    out
+}
+
+fn section(out: &mut String, title: &str, items: impl Iterator<Item = String>) {
+    let items = items.collect::<Vec<_>>();
+    if items.is_empty() {
+        return;
+    }
+    let _ = writeln!(out);
+    let _ = writeln!(out, "### {title}");
+    let _ = writeln!(out);
+    for item in items {
+        let _ = writeln!(out, "- {item}");
+    }
+}
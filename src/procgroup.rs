@@ -0,0 +1,120 @@
+//! Process-group bookkeeping for case commands.
+//!
+//! Every case is spawned as the leader of its own process group so that
+//! anything it forks can be found (and cleaned up) once the case returns.
+
+use std::{
+    fs, thread,
+    time::{Duration, Instant},
+};
+
+/// How long to keep reaping after SIGKILL before giving up on stragglers.
+const REAP_GRACE: Duration = Duration::from_secs(2);
+
+/// Makes the harness a child subreaper so orphaned grandchildren are
+/// re-parented to it instead of init, which lets us reap them ourselves.
+pub fn become_subreaper() {
+    #[cfg(target_os = "linux")]
+    unsafe {
+        libc::prctl(libc::PR_SET_CHILD_SUBREAPER, 1, 0, 0, 0);
+    }
+}
+
+/// Returns true when at least one process still belongs to `pgid`.
+pub fn group_alive(pgid: i32) -> bool {
+    unsafe { libc::kill(-pgid, 0) == 0 }
+}
+
+/// Lists the PIDs still in process group `pgid` by scanning `/proc`.
+///
+/// On platforms without `/proc` this returns an empty list even when
+/// [`group_alive`] reports survivors.
+pub fn group_members(pgid: i32) -> Vec<i32> {
+    let Ok(entries) = fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+    let mut pids = entries
+        .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse::<i32>().ok())
+        .filter(|pid| process_group_of(*pid) == Some(pgid))
+        .collect::<Vec<_>>();
+    pids.sort_unstable();
+    pids
+}
+
+/// Reads the process group out of `/proc/<pid>/stat` (field 5).
+fn process_group_of(pid: i32) -> Option<i32> {
+    let stat = fs::read_to_string(format!("/proc/{pid}/stat")).ok()?;
+    // The command name may contain spaces, so skip past its closing paren.
+    let rest = &stat[stat.rfind(')')? + 1..];
+    rest.split_whitespace().nth(2)?.parse().ok()
+}
+
+/// Copy of a running process's `/proc/<pid>/status` and `smaps_rollup`.
+#[derive(Debug)]
+pub struct ProcSnapshot {
+    pub status: String,
+    /// Absent on kernels older than 4.14.
+    pub smaps_rollup: Option<String>,
+    pub taken_at: Instant,
+}
+
+/// Reads a snapshot of `pid`. `None` without `/proc` or once the process has
+/// exited, as a zombie no longer has a memory map worth recording.
+pub fn snapshot(pid: i32) -> Option<ProcSnapshot> {
+    let status = fs::read_to_string(format!("/proc/{pid}/status")).ok()?;
+    let zombie = status
+        .lines()
+        .find_map(|line| line.strip_prefix("State:"))
+        .is_some_and(|state| matches!(state.trim_start().chars().next(), Some('Z' | 'X')));
+    if zombie {
+        return None;
+    }
+    Some(ProcSnapshot {
+        status,
+        smaps_rollup: fs::read_to_string(format!("/proc/{pid}/smaps_rollup")).ok(),
+        taken_at: Instant::now(),
+    })
+}
+
+/// Sends SIGKILL to every member of `pgid`.
+pub fn kill_group(pgid: i32) {
+    unsafe {
+        libc::kill(-pgid, libc::SIGKILL);
+    }
+}
+
+/// Reaps every exited child the harness has not collected, such as orphans
+/// that left their case's process group before being re-parented to us.
+/// Returns their PIDs. Must not run while any other child of the process may
+/// still be waited on (a running case, another run on another thread): it
+/// would take that child's exit status.
+pub fn reap_strays() -> Vec<i32> {
+    let mut reaped = Vec::new();
+    loop {
+        let mut status = 0;
+        let pid = unsafe { libc::waitpid(-1, &mut status, libc::WNOHANG) };
+        if pid > 0 {
+            reaped.push(pid);
+        } else if pid == 0 || std::io::Error::last_os_error().raw_os_error() != Some(libc::EINTR) {
+            return reaped;
+        }
+    }
+}
+
+/// Kills the whole group and reaps whatever has been re-parented to the
+/// harness. Returns once the group is gone or the grace period ends.
+pub fn kill_and_reap(pgid: i32) {
+    kill_group(pgid);
+    let deadline = Instant::now() + REAP_GRACE;
+    loop {
+        let mut status = 0;
+        let reaped = unsafe { libc::waitpid(-pgid, &mut status, libc::WNOHANG) };
+        if reaped > 0 {
+            continue;
+        }
+        if !group_alive(pgid) || Instant::now() >= deadline {
+            break;
+        }
+        thread::sleep(Duration::from_millis(20));
+    }
+}
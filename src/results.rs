@@ -0,0 +1,139 @@
+//! Collecting finished cases into the run's counters.
+//!
+//! [`ResultsAccumulator`] owns the per-status counters and the case details
+//! behind one mutex, so whichever thread finishes a case can record it and
+//! the counts always agree with the list of cases.
+
+use std::sync::{Mutex, MutexGuard};
+
+use crate::CaseDetail;
+
+/// Counters and case details of a finished (or interrupted) run.
+#[derive(Debug, Default)]
+pub struct CaseResults {
+    pub passed: usize,
+    pub failed: usize,
+    pub soft_failed: usize,
+    pub flaky_failed: usize,
+    pub skipped: usize,
+    /// In the order the cases were recorded.
+    pub cases: Vec<CaseDetail>,
+}
+
+#[derive(Debug, Default)]
+pub struct ResultsAccumulator {
+    results: Mutex<CaseResults>,
+}
+
+impl ResultsAccumulator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Counts `detail` under its status and appends it. Interrupted cases are
+    /// listed but not counted.
+    pub fn record(&self, detail: CaseDetail) {
+        let mut results = self.lock();
+        match detail.status.as_str() {
+            "passed" => results.passed += 1,
+            "failed" => results.failed += 1,
+            "soft_failed" => results.soft_failed += 1,
+            "flaky_failed" => results.flaky_failed += 1,
+            "skipped" => results.skipped += 1,
+            _ => {}
+        }
+        results.cases.push(detail);
+    }
+
+    /// Hard failures so far, for `--bail-after`.
+    pub fn failed(&self) -> usize {
+        self.lock().failed
+    }
+
+    /// Runs `read` on the cases recorded so far.
+    pub fn with_cases<R>(&self, read: impl FnOnce(&[CaseDetail]) -> R) -> R {
+        read(&self.lock().cases)
+    }
+
+    pub fn into_summary(self) -> CaseResults {
+        self.results
+            .into_inner()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn lock(&self) -> MutexGuard<'_, CaseResults> {
+        self.results
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+
+    fn detail(name: &str, status: &str) -> CaseDetail {
+        CaseDetail {
+            name: name.to_string(),
+            status: status.to_string(),
+            attempts: 1,
+            ..CaseDetail::default()
+        }
+    }
+
+    #[test]
+    fn counts_every_status_once() {
+        let accumulator = ResultsAccumulator::new();
+        for (name, status) in [
+            ("a", "passed"),
+            ("b", "failed"),
+            ("c", "soft_failed"),
+            ("d", "flaky_failed"),
+            ("e", "skipped"),
+            ("f", "interrupted"),
+            ("g", "passed"),
+        ] {
+            accumulator.record(detail(name, status));
+        }
+        assert_eq!(accumulator.failed(), 1);
+
+        let results = accumulator.into_summary();
+        assert_eq!(
+            (
+                results.passed,
+                results.failed,
+                results.soft_failed,
+                results.flaky_failed,
+                results.skipped
+            ),
+            (2, 1, 1, 1, 1)
+        );
+        let names = results
+            .cases
+            .iter()
+            .map(|case| case.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["a", "b", "c", "d", "e", "f", "g"]);
+    }
+
+    #[test]
+    fn concurrent_records_are_all_counted() {
+        let accumulator = ResultsAccumulator::new();
+        thread::scope(|scope| {
+            for worker in 0..4 {
+                let accumulator = &accumulator;
+                scope.spawn(move || {
+                    for idx in 0..25 {
+                        let status = if idx % 5 == 0 { "failed" } else { "passed" };
+                        accumulator.record(detail(&format!("{worker}-{idx}"), status));
+                    }
+                });
+            }
+        });
+        let results = accumulator.into_summary();
+        assert_eq!(results.cases.len(), 100);
+        assert_eq!((results.passed, results.failed), (80, 20));
+    }
+}
@@ -0,0 +1,137 @@
+//! `--retry-suite`: re-run a failing suite as a whole.
+//!
+//! Per-case retries cannot help when the environment itself was not ready,
+//! say a daemon that came up only after the first cases ran. With
+//! `--retry-suite N` a run that ends with failed cases is repeated, up to N
+//! more times, each attempt in its own run directory. A case counts as failed
+//! only if it failed in every attempt. The attempts are recorded in
+//! `suite_attempts.json` next to `last_run.json`.
+
+use std::{collections::BTreeSet, fs, path::Path};
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+use crate::{
+    KeepRun, RunOptions, RunStatus, SuiteSpec,
+    clock::Clock,
+    history::{self, PreviousRun},
+    logs_root, run_suite,
+};
+
+/// Written to the suite's log root after the last attempt.
+pub const ATTEMPTS_FILE: &str = "suite_attempts.json";
+
+#[derive(Debug, Serialize)]
+struct SuiteAttempts {
+    attempts: Vec<SuiteAttempt>,
+    /// Cases that failed in every attempt; empty when the retries cleared them all.
+    failed_every_attempt: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct SuiteAttempt {
+    attempt: u32,
+    run_id: String,
+    passed: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    failed: Vec<String>,
+}
+
+pub fn run(
+    suite: &SuiteSpec,
+    workspace: &Path,
+    options: &RunOptions,
+    clock: &dyn Clock,
+) -> Result<RunStatus> {
+    // Every attempt's directory is evidence; none may be cleaned up.
+    let mut options = options.clone();
+    if options.keep != KeepRun::Always {
+        println!("[retry-suite] keeping every attempt's run directory (--keep ignored)");
+        options.keep = KeepRun::Always;
+    }
+    let logs_root = logs_root(&options, suite, workspace)?;
+    let total = options.retry_suite + 1;
+    let mut attempts = Vec::new();
+    let mut failed_every_attempt: Option<BTreeSet<String>> = None;
+    let mut status = RunStatus::Passed;
+    for attempt in 1..=total {
+        status = run_suite(suite, workspace, &options, clock)?;
+        let (run_id, summary_path) = history::run_summary(&logs_root, "latest")?;
+        let failed = PreviousRun::load(&summary_path)
+            .with_context(|| format!("failed to read {}", summary_path.display()))?
+            .cases
+            .into_iter()
+            .filter(|case| case.status == "failed")
+            .map(|case| case.name)
+            .collect::<BTreeSet<_>>();
+        attempts.push(SuiteAttempt {
+            attempt,
+            run_id,
+            passed: status == RunStatus::Passed,
+            failed: failed.iter().cloned().collect(),
+        });
+        let still_failing = failed_every_attempt.get_or_insert_with(|| failed.clone());
+        still_failing.retain(|name| failed.contains(name));
+        // Setup errors, interruptions and the like are not ours to retry.
+        if status != RunStatus::TestsFailed || failed.is_empty() || still_failing.is_empty() {
+            break;
+        }
+        if attempt < total {
+            println!(
+                "[retry-suite] attempt {attempt} of {total}: {} failed ({}); re-running the whole suite",
+                failed.len(),
+                failed.iter().cloned().collect::<Vec<_>>().join(", ")
+            );
+        }
+    }
+
+    let failed_every_attempt = failed_every_attempt
+        .unwrap_or_default()
+        .into_iter()
+        .collect::<Vec<_>>();
+    let recovered = attempts
+        .first()
+        .map(|first| {
+            first
+                .failed
+                .iter()
+                .filter(|name| !failed_every_attempt.contains(name))
+                .cloned()
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    let ran = attempts.len();
+    let record = SuiteAttempts {
+        attempts,
+        failed_every_attempt,
+    };
+    let path = logs_root.join(ATTEMPTS_FILE);
+    fs::write(&path, serde_json::to_string_pretty(&record)?)
+        .with_context(|| format!("failed to write {}", path.display()))?;
+
+    if ran > 1 {
+        if !recovered.is_empty() {
+            println!(
+                "[retry-suite] passed on a later attempt: {}",
+                recovered.join(", ")
+            );
+        }
+        if record.failed_every_attempt.is_empty() {
+            println!("[retry-suite] no case failed in all {ran} attempts");
+        } else {
+            println!(
+                "[retry-suite] failed in all {ran} attempts: {}",
+                record.failed_every_attempt.join(", ")
+            );
+        }
+    }
+    // The last attempt may fail in cases that passed earlier; those are not real failures.
+    if status == RunStatus::TestsFailed
+        && record.failed_every_attempt.is_empty()
+        && !recovered.is_empty()
+    {
+        status = RunStatus::Passed;
+    }
+    Ok(status)
+}
@@ -0,0 +1,201 @@
+//! `--serve`: a tiny HTTP endpoint exposing a run's progress as JSON.
+//!
+//! `GET /status` returns the counters of the in-progress summary and `GET
+//! /cases` every selected case, finished ones with their full detail. The
+//! server lives on a background thread and is stopped when dropped.
+
+use std::{
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread::{self, JoinHandle},
+};
+
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use serde_json::{Value, json};
+use tiny_http::{Header, Method, Response, Server};
+
+use crate::CaseDetail;
+
+/// What the endpoints report; replaced wholesale on every update.
+struct LiveRun {
+    suite: String,
+    started_at: DateTime<Local>,
+    case_names: Vec<String>,
+    finished_cases: Vec<Value>,
+    counts: Counts,
+    running: Option<String>,
+    finished: bool,
+}
+
+#[derive(Default, Clone, Copy, Serialize)]
+struct Counts {
+    passed: usize,
+    failed: usize,
+    soft_failed: usize,
+    flaky_failed: usize,
+    skipped: usize,
+}
+
+pub struct StatusServer {
+    server: Arc<Server>,
+    live: Arc<Mutex<LiveRun>>,
+    stopping: Arc<AtomicBool>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl StatusServer {
+    /// Binds `addr` (e.g. `127.0.0.1:8080`, port 0 picks a free one) and
+    /// starts answering requests.
+    pub fn start(
+        addr: &str,
+        suite: &str,
+        started_at: DateTime<Local>,
+        case_names: Vec<String>,
+    ) -> Result<Self> {
+        let server = Arc::new(
+            Server::http(addr)
+                .map_err(|err| anyhow!("failed to start --serve server on {addr}: {err}"))?,
+        );
+        let live = Arc::new(Mutex::new(LiveRun {
+            suite: suite.to_string(),
+            started_at,
+            case_names,
+            finished_cases: Vec::new(),
+            counts: Counts::default(),
+            running: None,
+            finished: false,
+        }));
+        let stopping = Arc::new(AtomicBool::new(false));
+        let worker = thread::spawn({
+            let server = Arc::clone(&server);
+            let live = Arc::clone(&live);
+            let stopping = Arc::clone(&stopping);
+            move || {
+                loop {
+                    match server.recv() {
+                        Ok(request) => respond(request, &live),
+                        Err(_) if stopping.load(Ordering::SeqCst) => break,
+                        Err(_) => continue,
+                    }
+                }
+            }
+        });
+        Ok(Self {
+            server,
+            live,
+            stopping,
+            worker: Some(worker),
+        })
+    }
+
+    /// The address actually bound, for the startup message.
+    pub fn addr(&self) -> String {
+        self.server.server_addr().to_string()
+    }
+
+    /// Publishes the cases finished so far and the one about to run.
+    pub fn update(&self, finished: &[CaseDetail], running: Option<&str>) {
+        let mut counts = Counts::default();
+        for case in finished {
+            match case.status.as_str() {
+                "passed" => counts.passed += 1,
+                "failed" => counts.failed += 1,
+                "soft_failed" => counts.soft_failed += 1,
+                "flaky_failed" => counts.flaky_failed += 1,
+                "skipped" => counts.skipped += 1,
+                _ => {}
+            }
+        }
+        let finished_cases = finished
+            .iter()
+            .map(|case| serde_json::to_value(case).unwrap_or(Value::Null))
+            .collect();
+        let mut live = self
+            .live
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        live.finished_cases = finished_cases;
+        live.counts = counts;
+        live.running = running.map(str::to_string);
+    }
+
+    /// Marks the run complete; the final state stays visible until drop.
+    pub fn finish(&self, finished: &[CaseDetail]) {
+        self.update(finished, None);
+        self.live
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .finished = true;
+    }
+}
+
+impl Drop for StatusServer {
+    fn drop(&mut self) {
+        self.stopping.store(true, Ordering::SeqCst);
+        self.server.unblock();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+fn respond(request: tiny_http::Request, live: &Mutex<LiveRun>) {
+    let body = {
+        let live = live.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        match (
+            request.method(),
+            request.url().split('?').next().unwrap_or_default(),
+        ) {
+            (Method::Get, "/status") => Some(status_json(&live)),
+            (Method::Get, "/cases") => Some(cases_json(&live)),
+            _ => None,
+        }
+    };
+    let response = match body {
+        Some(body) => Response::from_string(body.to_string()).with_header(json_header()),
+        None => Response::from_string(json!({ "error": "not found" }).to_string())
+            .with_header(json_header())
+            .with_status_code(404),
+    };
+    // A client hanging up early is its own problem.
+    let _ = request.respond(response);
+}
+
+fn json_header() -> Header {
+    Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..])
+        .expect("static header is valid")
+}
+
+fn status_json(live: &LiveRun) -> Value {
+    json!({
+        "suite": live.suite,
+        "started_at": live.started_at,
+        "total": live.case_names.len(),
+        "completed": live.finished_cases.len(),
+        "passed": live.counts.passed,
+        "failed": live.counts.failed,
+        "soft_failed": live.counts.soft_failed,
+        "flaky_failed": live.counts.flaky_failed,
+        "skipped": live.counts.skipped,
+        "running": live.running,
+        "finished": live.finished,
+    })
+}
+
+/// Finished cases in full, then the running one, then the rest as pending.
+fn cases_json(live: &LiveRun) -> Value {
+    let mut cases = live.finished_cases.clone();
+    for name in &live.case_names[live.finished_cases.len().min(live.case_names.len())..] {
+        let status = if live.running.as_deref() == Some(name.as_str()) {
+            "running"
+        } else {
+            "pending"
+        };
+        cases.push(json!({ "name": name, "status": status }));
+    }
+    Value::Array(cases)
+}
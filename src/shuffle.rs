@@ -0,0 +1,165 @@
+//! Seeded case-order randomization for `--shuffle` and `--shuffle-groups`.
+//!
+//! The generator is a small SplitMix64 so a seed printed by one run
+//! reproduces the same order on any machine.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::TestCase;
+
+/// How the order was randomized, recorded in the summary for reruns.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShuffleInfo {
+    /// `cases` for `--shuffle`, `groups` for `--shuffle-groups`.
+    pub mode: String,
+    /// Pass to `--seed` to reproduce the order.
+    pub seed: u64,
+    /// Seed used for the order of the groups themselves (derived from `seed`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group_seed: Option<u64>,
+    /// Text `seed` was hashed from, with `--seed-from`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed_from: Option<String>,
+}
+
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    fn shuffle<T>(&mut self, items: &mut [T]) {
+        for idx in (1..items.len()).rev() {
+            let pick = (self.next() % (idx as u64 + 1)) as usize;
+            items.swap(idx, pick);
+        }
+    }
+}
+
+/// A seed for runs that did not pass `--seed`.
+pub fn fresh_seed() -> u64 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_nanos() as u64);
+    SplitMix64(nanos ^ u64::from(std::process::id())).next()
+}
+
+/// A seed for `--seed-from`: 64-bit FNV-1a of `text`, mixed once more so
+/// similar strings (consecutive dates, say) give unrelated seeds. Unlike
+/// std's hasher its output never changes between Rust releases.
+pub fn seed_from(text: &str) -> u64 {
+    let hash = text.bytes().fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    });
+    SplitMix64(hash).next()
+}
+
+/// Shuffles every case independently.
+pub fn shuffle_cases(cases: &mut [TestCase], seed: u64) -> ShuffleInfo {
+    SplitMix64(seed).shuffle(cases);
+    ShuffleInfo {
+        mode: "cases".to_string(),
+        seed,
+        group_seed: None,
+        seed_from: None,
+    }
+}
+
+/// Shuffles the order of groups and the order of cases inside each group,
+/// keeping every group contiguous. Cases without a group move on their own.
+pub fn shuffle_groups(cases: Vec<TestCase>, seed: u64) -> (Vec<TestCase>, ShuffleInfo) {
+    let group_seed = SplitMix64(seed ^ 0x6a09_e667_f3bc_c908).next();
+    let mut blocks: Vec<Vec<TestCase>> = Vec::new();
+    for case in cases {
+        let existing = case.group.as_ref().and_then(|group| {
+            blocks
+                .iter()
+                .position(|block| block[0].group.as_ref() == Some(group))
+        });
+        match existing {
+            Some(idx) => blocks[idx].push(case),
+            None => blocks.push(vec![case]),
+        }
+    }
+
+    let mut within = SplitMix64(seed);
+    for block in &mut blocks {
+        within.shuffle(block);
+    }
+    SplitMix64(group_seed).shuffle(&mut blocks);
+    let info = ShuffleInfo {
+        mode: "groups".to_string(),
+        seed,
+        group_seed: Some(group_seed),
+        seed_from: None,
+    };
+    (blocks.into_iter().flatten().collect(), info)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn case(name: &str, group: Option<&str>) -> TestCase {
+        let group = group.map_or(String::new(), |group| format!("group = \"{group}\"\n"));
+        toml::from_str(&format!("name = \"{name}\"\npath = \"run.sh\"\n{group}")).unwrap()
+    }
+
+    fn names(cases: &[TestCase]) -> Vec<&str> {
+        cases.iter().map(|case| case.name.as_str()).collect()
+    }
+
+    // Summaries record the seed so an order can be rerun later; these orders
+    // must not change between harness versions.
+    #[test]
+    fn generator_matches_splitmix64() {
+        assert_eq!(SplitMix64(0).next(), 0xe220_a839_7b1d_cdaf);
+    }
+
+    #[test]
+    fn fixed_seed_gives_a_fixed_case_order() {
+        let mut cases = ["a", "b", "c", "d", "e", "f"].map(|name| case(name, None));
+        shuffle_cases(&mut cases, 42);
+        assert_eq!(names(&cases), ["e", "d", "a", "c", "f", "b"]);
+    }
+
+    #[test]
+    fn fixed_seed_gives_a_fixed_group_order() {
+        let cases = vec![
+            case("fs-1", Some("fs")),
+            case("net-1", Some("net")),
+            case("boot", None),
+            case("fs-2", Some("fs")),
+            case("net-2", Some("net")),
+            case("fs-3", Some("fs")),
+        ];
+        let (cases, info) = shuffle_groups(cases, 42);
+        assert_eq!(
+            names(&cases),
+            ["net-2", "net-1", "fs-1", "fs-3", "fs-2", "boot"]
+        );
+        assert_eq!(info.group_seed, Some(3_884_763_827_766_466_949));
+
+        // Each group still runs as one block.
+        for group in ["fs", "net"] {
+            let positions = cases
+                .iter()
+                .enumerate()
+                .filter(|(_, case)| case.group.as_deref() == Some(group))
+                .map(|(idx, _)| idx)
+                .collect::<Vec<_>>();
+            assert_eq!(
+                positions.last().unwrap() - positions[0] + 1,
+                positions.len(),
+                "{group}: {positions:?}"
+            );
+        }
+    }
+}
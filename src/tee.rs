@@ -0,0 +1,247 @@
+//! `--tee`: a plain-text transcript of the console output.
+//!
+//! The process's stdout and stderr are swapped for pipes whose reader threads
+//! forward every byte to the real console and into the transcript. The
+//! transcript drops color codes and replays the cursor-up/clear-line rewrites
+//! of the case boxes, so it reads like the console looked once a run settled.
+
+use std::{
+    fs::File,
+    io::{self, IsTerminal, Read, Write},
+    os::fd::{FromRawFd, RawFd},
+    path::Path,
+    sync::{Arc, Mutex, OnceLock},
+    thread::{self, JoinHandle},
+};
+
+use anyhow::{Context, Result};
+
+/// Lines kept back from the file because the console may still rewrite them.
+const REWRITABLE_LINES: usize = 64;
+
+/// Whether stdout was a terminal before `--tee` replaced it with a pipe.
+static STDOUT_IS_TERMINAL: OnceLock<bool> = OnceLock::new();
+
+/// Whether the console (not the tee pipe) is an interactive terminal.
+pub fn stdout_is_terminal() -> bool {
+    *STDOUT_IS_TERMINAL.get_or_init(|| io::stdout().is_terminal())
+}
+
+pub struct Tee {
+    transcript: Arc<Mutex<Transcript>>,
+    streams: Vec<(RawFd, RawFd, JoinHandle<()>)>,
+}
+
+impl Tee {
+    /// Starts copying stdout and stderr into a fresh transcript at `path`.
+    pub fn start(path: &Path) -> Result<Self> {
+        let file = File::create(path)
+            .with_context(|| format!("failed to create --tee transcript {}", path.display()))?;
+        // Decide color and box redrawing from the real console, before it turns into a pipe.
+        stdout_is_terminal();
+        colored::control::set_override(colored::control::SHOULD_COLORIZE.should_colorize());
+
+        let transcript = Arc::new(Mutex::new(Transcript::new(file)));
+        let mut tee = Self {
+            transcript,
+            streams: Vec::new(),
+        };
+        for (stream, fd) in [libc::STDOUT_FILENO, libc::STDERR_FILENO]
+            .into_iter()
+            .enumerate()
+        {
+            let stream = tee_fd(fd, stream, Arc::clone(&tee.transcript))
+                .with_context(|| format!("failed to redirect descriptor {fd} for --tee"))?;
+            tee.streams.push(stream);
+        }
+        Ok(tee)
+    }
+}
+
+impl Drop for Tee {
+    fn drop(&mut self) {
+        let _ = io::stdout().flush();
+        let _ = io::stderr().flush();
+        for (fd, saved, reader) in self.streams.drain(..) {
+            // SAFETY: puts back the descriptor saved in `tee_fd`; this closes the
+            // pipe's last write end, so the reader sees EOF.
+            unsafe {
+                libc::dup2(saved, fd);
+                libc::close(saved);
+            }
+            let _ = reader.join();
+        }
+        self.transcript
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .finish();
+    }
+}
+
+/// Points `fd` at a new pipe and starts the thread draining it. Returns the
+/// descriptor, a duplicate of its original target and the thread.
+fn tee_fd(
+    fd: RawFd,
+    stream: usize,
+    transcript: Arc<Mutex<Transcript>>,
+) -> io::Result<(RawFd, RawFd, JoinHandle<()>)> {
+    let mut pipe = [0; 2];
+    // SAFETY: plain descriptor juggling; every descriptor created here is
+    // either owned by a `File` below or closed on the error paths.
+    let (saved, console) = unsafe {
+        if libc::pipe2(pipe.as_mut_ptr(), libc::O_CLOEXEC) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let saved = libc::fcntl(fd, libc::F_DUPFD_CLOEXEC, 0);
+        let console = if saved >= 0 {
+            libc::fcntl(saved, libc::F_DUPFD_CLOEXEC, 0)
+        } else {
+            -1
+        };
+        if console < 0 || libc::dup2(pipe[1], fd) < 0 {
+            let err = io::Error::last_os_error();
+            for owned in [pipe[0], pipe[1], saved, console] {
+                if owned >= 0 {
+                    libc::close(owned);
+                }
+            }
+            return Err(err);
+        }
+        libc::close(pipe[1]);
+        (saved, console)
+    };
+    // SAFETY: the read end and the second duplicate of the console belong to
+    // the thread, which may still be writing after `Drop` restored `fd`.
+    let mut reader = unsafe { File::from_raw_fd(pipe[0]) };
+    let mut console = unsafe { File::from_raw_fd(console) };
+    let thread = thread::spawn(move || {
+        let mut buf = [0u8; 8192];
+        loop {
+            match reader.read(&mut buf) {
+                Ok(0) => break,
+                Ok(len) => {
+                    let _ = console.write_all(&buf[..len]);
+                    transcript
+                        .lock()
+                        .unwrap_or_else(|poisoned| poisoned.into_inner())
+                        .feed(stream, &buf[..len]);
+                }
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(_) => break,
+            }
+        }
+    });
+    Ok((fd, saved, thread))
+}
+
+/// Console bytes reduced to plain lines.
+struct Transcript {
+    file: File,
+    /// Finished lines the console could still move back up to.
+    lines: Vec<Vec<u8>>,
+    /// Unfinished line of stdout and of stderr, kept apart so the two never
+    /// end up spliced into one line.
+    partial: [Partial; 2],
+}
+
+#[derive(Default)]
+struct Partial {
+    current: Vec<u8>,
+    escape: Escape,
+}
+
+#[derive(Default)]
+enum Escape {
+    #[default]
+    None,
+    /// Saw ESC.
+    Start,
+    /// Inside a CSI sequence, collecting its parameters.
+    Csi(Vec<u8>),
+}
+
+impl Transcript {
+    fn new(file: File) -> Self {
+        Self {
+            file,
+            lines: Vec::new(),
+            partial: Default::default(),
+        }
+    }
+
+    fn feed(&mut self, stream: usize, bytes: &[u8]) {
+        for &byte in bytes {
+            match std::mem::take(&mut self.partial[stream].escape) {
+                Escape::Start if byte == b'[' => {
+                    self.partial[stream].escape = Escape::Csi(Vec::new())
+                }
+                // Other escapes are two bytes long; drop both.
+                Escape::Start => {}
+                Escape::Csi(mut params) => {
+                    if (0x40..=0x7e).contains(&byte) {
+                        self.apply_csi(stream, &params, byte);
+                    } else {
+                        params.push(byte);
+                        self.partial[stream].escape = Escape::Csi(params);
+                    }
+                }
+                Escape::None => match byte {
+                    0x1b => self.partial[stream].escape = Escape::Start,
+                    b'\n' => {
+                        let line = std::mem::take(&mut self.partial[stream].current);
+                        self.lines.push(line);
+                        self.flush_settled();
+                    }
+                    b'\r' => self.partial[stream].current.clear(),
+                    _ => self.partial[stream].current.push(byte),
+                },
+            }
+        }
+    }
+
+    /// Colors are dropped; cursor-up and clear-line are replayed on the lines.
+    fn apply_csi(&mut self, stream: usize, params: &[u8], command: u8) {
+        let current = &mut self.partial[stream].current;
+        match command {
+            b'A' => {
+                let count = std::str::from_utf8(params)
+                    .ok()
+                    .and_then(|count| count.parse::<usize>().ok())
+                    .unwrap_or(1);
+                for _ in 0..count {
+                    match self.lines.pop() {
+                        Some(line) => *current = line,
+                        None => break,
+                    }
+                }
+            }
+            b'K' if params == b"2" => current.clear(),
+            _ => {}
+        }
+    }
+
+    fn flush_settled(&mut self) {
+        if self.lines.len() > REWRITABLE_LINES {
+            let settled = self.lines.len() - REWRITABLE_LINES;
+            for line in self.lines.drain(..settled) {
+                let _ = self.file.write_all(&line);
+                let _ = self.file.write_all(b"\n");
+            }
+        }
+    }
+
+    fn finish(&mut self) {
+        for line in self.lines.drain(..) {
+            let _ = self.file.write_all(&line);
+            let _ = self.file.write_all(b"\n");
+        }
+        for partial in &mut self.partial {
+            if !partial.current.is_empty() {
+                let _ = self.file.write_all(&partial.current);
+                let _ = self.file.write_all(b"\n");
+                partial.current.clear();
+            }
+        }
+        let _ = self.file.flush();
+    }
+}
@@ -0,0 +1,140 @@
+//! `suggest-timeouts`: timeout values grounded in recent runs.
+//!
+//! Reads the summaries of the most recent runs, takes each selected case's
+//! longest passing duration, and suggests `timeout_secs` as a multiple of it,
+//! rounded up. Cases whose configured timeout leaves little headroom over
+//! what they already needed are called out, since they are the ones that
+//! start timing out on a slower machine.
+
+use std::{collections::HashMap, path::Path};
+
+use anyhow::{Result, bail};
+use colored::Colorize;
+
+use crate::{CaseStatus, RunStatus, TestCase, history};
+
+/// Suggested timeout as a multiple of the longest observed passing run.
+const SUGGEST_FACTOR: f64 = 3.0;
+/// Suggestions are rounded up to a multiple of this many seconds.
+const ROUND_TO_SECS: u64 = 5;
+/// A configured timeout below this multiple of the observed maximum is "close".
+const CLOSE_FACTOR: f64 = 1.5;
+
+/// Longest passing duration of a case and how many runs it was seen passing in.
+struct Observed {
+    max_ms: u128,
+    runs: usize,
+}
+
+/// Prints a suggested `timeout_secs` for every case in `cases`, reading up to
+/// `history_runs` of the latest runs under `logs_root`.
+pub fn suggest(
+    logs_root: &Path,
+    cases: &[TestCase],
+    default_timeout_secs: u64,
+    history_runs: usize,
+) -> Result<RunStatus> {
+    let runs = history::recent_runs(logs_root, history_runs);
+    if runs.is_empty() {
+        bail!(
+            "no run with a {} in {}",
+            history::RUN_SUMMARY_FILE,
+            logs_root.display()
+        );
+    }
+    // Only passing runs count: a case killed at its timeout would otherwise
+    // suggest a multiple of that timeout back.
+    let mut observed: HashMap<&str, Observed> = HashMap::new();
+    for (_, run) in &runs {
+        for case in &run.cases {
+            if case.status != CaseStatus::Passed.as_str() {
+                continue;
+            }
+            let entry = observed
+                .entry(case.key())
+                .or_insert(Observed { max_ms: 0, runs: 0 });
+            entry.max_ms = entry.max_ms.max(case.duration_ms);
+            entry.runs += 1;
+        }
+    }
+
+    println!(
+        "[suggest-timeouts] {} case(s), {} run(s) from {} to {} ({SUGGEST_FACTOR}x the longest passing run, rounded up to {ROUND_TO_SECS}s)",
+        cases.len(),
+        runs.len(),
+        runs[0].0,
+        runs[runs.len() - 1].0
+    );
+    let width = cases
+        .iter()
+        .map(|case| case.name.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max("case".len());
+    println!(
+        "  {:<width$}  {:>12}  {:>4}  {:>8}  {:>9}",
+        "case", "observed max", "runs", "current", "suggested"
+    );
+    let mut close = Vec::new();
+    for case in cases {
+        let key = case.id.as_deref().unwrap_or(&case.name);
+        let current_secs = case.timeout_secs.unwrap_or(default_timeout_secs);
+        let Some(seen) = observed.get(key) else {
+            println!(
+                "  {:<width$}  {:>12}  {:>4}  {:>8}  {}",
+                case.name,
+                "-",
+                0,
+                format!("{current_secs}s"),
+                format!("{:>9}", "-").dimmed()
+            );
+            continue;
+        };
+        let max_secs = seen.max_ms as f64 / 1000.0;
+        let suggested_secs = suggested_timeout_secs(seen.max_ms);
+        let suggested = format!("{:>9}", format!("{suggested_secs}s"));
+        let suggested = if suggested_secs == current_secs {
+            suggested.normal()
+        } else {
+            suggested.bright_white().bold()
+        };
+        println!(
+            "  {:<width$}  {:>12}  {:>4}  {:>8}  {}",
+            case.name,
+            format!("{max_secs:.2}s"),
+            seen.runs,
+            format!("{current_secs}s"),
+            suggested
+        );
+        if (current_secs as f64) < max_secs * CLOSE_FACTOR {
+            close.push(format!(
+                "case {}: timeout {current_secs}s is within {CLOSE_FACTOR}x of its longest passing run ({max_secs:.2}s); consider {suggested_secs}s",
+                case.name
+            ));
+        }
+    }
+    for warning in &close {
+        println!("{} {warning}", "warning:".yellow());
+    }
+    Ok(RunStatus::Passed)
+}
+
+/// `SUGGEST_FACTOR` times `max_ms`, rounded up to whole `ROUND_TO_SECS` steps.
+fn suggested_timeout_secs(max_ms: u128) -> u64 {
+    let secs = (max_ms as f64 / 1000.0 * SUGGEST_FACTOR).ceil() as u64;
+    secs.div_ceil(ROUND_TO_SECS).max(1) * ROUND_TO_SECS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn suggestions_round_up_to_whole_steps() {
+        assert_eq!(suggested_timeout_secs(0), 5);
+        assert_eq!(suggested_timeout_secs(50), 5);
+        assert_eq!(suggested_timeout_secs(1_700), 10);
+        assert_eq!(suggested_timeout_secs(10_000), 30);
+        assert_eq!(suggested_timeout_secs(10_001), 35);
+    }
+}
@@ -0,0 +1,101 @@
+//! `--trace`: the run as a Chrome trace (`chrome://tracing`, Perfetto).
+//!
+//! Every case, and the build step, becomes one complete (`X`) event placed
+//! by its offset from the suite start. Cases whose intervals overlap land on
+//! separate tracks, so a parallel run shows one lane per concurrent case.
+
+use std::{fs, path::Path};
+
+use anyhow::Result;
+use serde_json::{Value, json};
+
+use crate::RunSummary;
+
+/// Track of the build step; cases use the tracks after it.
+const BUILD_TRACK: usize = 0;
+
+pub fn write(path: &Path, summary: &RunSummary) -> Result<()> {
+    let mut events = vec![metadata("process_name", 0, &summary.suite)];
+    if let Some(build) = &summary.build
+        && !build.skipped
+    {
+        events.push(metadata("thread_name", BUILD_TRACK, "build"));
+        events.push(json!({
+            "name": build.script,
+            "cat": "build",
+            "ph": "X",
+            "ts": build.started_offset_ms * 1000,
+            "dur": build.duration_ms * 1000,
+            "pid": 0,
+            "tid": BUILD_TRACK,
+            "args": {
+                "exit_code": build.exit_code,
+                "attempts": build.attempts,
+                "success": build.success,
+            },
+        }));
+    }
+
+    // Skipped cases never ran and have nothing to show.
+    let mut cases = summary
+        .cases
+        .iter()
+        .filter(|case| case.status != "skipped")
+        .collect::<Vec<_>>();
+    cases.sort_by_key(|case| case.started_offset_ms);
+    // End offset of the last case on each track.
+    let mut tracks: Vec<u128> = Vec::new();
+    for case in cases {
+        let end = case.started_offset_ms + case.duration_ms;
+        let track = match tracks
+            .iter()
+            .position(|&busy_until| busy_until <= case.started_offset_ms)
+        {
+            Some(track) => {
+                tracks[track] = end;
+                track
+            }
+            None => {
+                tracks.push(end);
+                events.push(metadata(
+                    "thread_name",
+                    tracks.len(),
+                    &format!("cases #{}", tracks.len()),
+                ));
+                tracks.len() - 1
+            }
+        };
+        events.push(json!({
+            "name": case.name,
+            "cat": case.group.as_deref().unwrap_or("case"),
+            "ph": "X",
+            "ts": case.started_offset_ms * 1000,
+            "dur": case.duration_ms * 1000,
+            "pid": 0,
+            "tid": track + 1,
+            "args": {
+                "status": case.status,
+                "exit_code": case.exit_code,
+                "attempts": case.attempts,
+                "log": case.log_path,
+            },
+        }));
+    }
+
+    let trace = json!({
+        "traceEvents": events,
+        "displayTimeUnit": "ms",
+    });
+    fs::write(path, serde_json::to_string(&trace)?)?;
+    Ok(())
+}
+
+fn metadata(kind: &str, tid: usize, name: &str) -> Value {
+    json!({
+        "name": kind,
+        "ph": "M",
+        "pid": 0,
+        "tid": tid,
+        "args": { "name": name },
+    })
+}
@@ -0,0 +1,234 @@
+//! `--watch`: re-run a suite whenever its manifest or case scripts change.
+//!
+//! After the initial run the harness watches the manifest's directory and the
+//! directories of the case scripts. A burst of changes is debounced into one
+//! re-run of the affected cases (the whole suite when the manifest or build
+//! script changed), each in its own run directory. Pressing a key or Ctrl-C
+//! while waiting stops watching.
+
+use std::{
+    collections::HashSet,
+    io::{self, IsTerminal, Read},
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    thread,
+    time::Duration,
+};
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::{
+    RunOptions, RunStatus, SuiteSpec,
+    changed::{self, ChangeSelection},
+    clock::SystemClock,
+    interrupt, rel_path, resolve_manifest_path, run_selection, select_cases,
+};
+
+/// Quiet period that ends a burst of file changes.
+const DEBOUNCE: Duration = Duration::from_millis(300);
+/// How often the idle loop checks for a pending interrupt.
+const POLL: Duration = Duration::from_millis(100);
+
+enum Signal {
+    Changed(Vec<PathBuf>),
+    Quit,
+}
+
+pub fn run(suite: &SuiteSpec, workspace: &Path, options: &RunOptions) -> Result<RunStatus> {
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher({
+        let tx = tx.clone();
+        move |event: notify::Result<notify::Event>| {
+            if let Ok(event) = event
+                && matches!(
+                    event.kind,
+                    EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+                )
+            {
+                let _ = tx.send(Signal::Changed(event.paths));
+            }
+        }
+    })
+    .context("failed to start the file watcher")?;
+    let _keys = KeyListener::start(tx);
+    interrupt::install();
+
+    let mut watched = HashSet::new();
+    let mut status = run_once(suite, workspace, options, None);
+    let mut ran = true;
+    loop {
+        if interrupt::requested() {
+            break;
+        }
+        watch_suite_paths(&mut watcher, &mut watched, suite, workspace, options);
+        if ran {
+            println!(
+                "{}",
+                "[watch] waiting for changes (press any key or Ctrl-C to stop)".bright_cyan()
+            );
+        }
+        let Some(changed) = wait_for_changes(&rx) else {
+            break;
+        };
+        let changed = changed
+            .iter()
+            .map(|path| rel_path(path, workspace).display().to_string())
+            .collect::<Vec<_>>();
+        let next = run_once(suite, workspace, options, Some(&changed));
+        ran = next.is_some();
+        status = next.or(status);
+    }
+    println!("{}", "[watch] stopped".bright_cyan());
+    Ok(status.unwrap_or(RunStatus::Passed))
+}
+
+/// Runs the suite, or with `changed` only the cases those files affect.
+/// Errors are reported and swallowed so a broken edit does not end the session.
+fn run_once(
+    suite: &SuiteSpec,
+    workspace: &Path,
+    options: &RunOptions,
+    changed: Option<&[String]>,
+) -> Option<RunStatus> {
+    let result = select_cases(suite, workspace, options).and_then(|mut selection| {
+        if let Some(changed) = changed {
+            let manifest_file =
+                resolve_manifest_path(workspace, suite, options.manifest.as_deref())?;
+            let mut suite_files = vec![rel_path(&manifest_file, workspace).display().to_string()];
+            suite_files.extend(selection.manifest.build_script.clone());
+            match changed::select(&selection.cases, changed, &suite_files)? {
+                ChangeSelection::Everything(trigger) => {
+                    println!("[watch] {trigger} changed; re-running the full suite");
+                }
+                ChangeSelection::Cases(cases) if cases.is_empty() => return Ok(None),
+                ChangeSelection::Cases(cases) => {
+                    println!(
+                        "[watch] re-running {} affected of {} cases",
+                        cases.len(),
+                        selection.cases.len()
+                    );
+                    selection.cases = cases;
+                }
+            }
+        }
+        run_selection(suite, workspace, options, selection, &SystemClock).map(Some)
+    });
+    match result {
+        Ok(status) => status,
+        Err(err) => {
+            eprintln!("{} {err:#}", "[watch] run failed:".bright_red());
+            Some(RunStatus::HarnessError)
+        }
+    }
+}
+
+/// Adds watches for the manifest directory and every case script directory
+/// not watched yet; paths that do not exist are picked up on a later pass.
+fn watch_suite_paths(
+    watcher: &mut RecommendedWatcher,
+    watched: &mut HashSet<PathBuf>,
+    suite: &SuiteSpec,
+    workspace: &Path,
+    options: &RunOptions,
+) {
+    let mut wanted = Vec::new();
+    if let Ok(manifest_file) = resolve_manifest_path(workspace, suite, options.manifest.as_deref())
+        && let Some(dir) = manifest_file.parent()
+    {
+        wanted.push((dir.to_path_buf(), RecursiveMode::Recursive));
+    }
+    if let Ok(selection) = select_cases(suite, workspace, options) {
+        let scripts = selection
+            .manifest
+            .cases
+            .iter()
+            .map(|case| case.path.as_str())
+            .chain(selection.manifest.build_script.as_deref());
+        for script in scripts {
+            if let Some(dir) = workspace.join(script).parent() {
+                wanted.push((dir.to_path_buf(), RecursiveMode::NonRecursive));
+            }
+        }
+    }
+    for (dir, mode) in wanted {
+        if !watched.contains(&dir) && watcher.watch(&dir, mode).is_ok() {
+            watched.insert(dir);
+        }
+    }
+}
+
+/// Blocks until a burst of changes has settled and returns the changed paths,
+/// or `None` once the user asked to stop.
+fn wait_for_changes(rx: &Receiver<Signal>) -> Option<Vec<PathBuf>> {
+    let mut changed = Vec::new();
+    loop {
+        let timeout = if changed.is_empty() { POLL } else { DEBOUNCE };
+        match rx.recv_timeout(timeout) {
+            Ok(Signal::Changed(paths)) => {
+                for path in paths {
+                    if !changed.contains(&path) {
+                        changed.push(path);
+                    }
+                }
+            }
+            Ok(Signal::Quit) | Err(RecvTimeoutError::Disconnected) => return None,
+            Err(RecvTimeoutError::Timeout) if !changed.is_empty() => return Some(changed),
+            Err(RecvTimeoutError::Timeout) => {}
+        }
+        if interrupt::requested() {
+            return None;
+        }
+    }
+}
+
+/// Reads single key presses from an interactive stdin, turning off line
+/// buffering for as long as it lives.
+struct KeyListener {
+    saved: Option<libc::termios>,
+}
+
+impl KeyListener {
+    fn start(tx: Sender<Signal>) -> Self {
+        if !io::stdin().is_terminal() {
+            return Self { saved: None };
+        }
+        let saved = cbreak();
+        thread::spawn(move || {
+            let mut byte = [0u8; 1];
+            if io::stdin().read(&mut byte).is_ok() {
+                let _ = tx.send(Signal::Quit);
+            }
+        });
+        Self { saved }
+    }
+}
+
+impl Drop for KeyListener {
+    fn drop(&mut self) {
+        if let Some(saved) = &self.saved {
+            // SAFETY: restores the attributes read from the same descriptor.
+            unsafe {
+                libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, saved);
+            }
+        }
+    }
+}
+
+/// Switches stdin to non-canonical mode (echo and signals unchanged) and
+/// returns the previous attributes.
+fn cbreak() -> Option<libc::termios> {
+    // SAFETY: `termios` is plain data and both calls only touch stdin's attributes.
+    unsafe {
+        let mut saved = std::mem::zeroed::<libc::termios>();
+        if libc::tcgetattr(libc::STDIN_FILENO, &mut saved) != 0 {
+            return None;
+        }
+        let mut raw = saved;
+        raw.c_lflag &= !libc::ICANON;
+        raw.c_cc[libc::VMIN] = 1;
+        raw.c_cc[libc::VTIME] = 0;
+        (libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &raw) == 0).then_some(saved)
+    }
+}
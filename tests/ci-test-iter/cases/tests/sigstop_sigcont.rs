@@ -9,9 +9,7 @@
 //! - Using waitpid() with WCONTINUED to detect that the child has resumed.
 //! - Terminating the child and cleaning up resources.
 
-use libc::{
-    fork, kill, waitpid, ECHILD, SIGCONT, SIGSTOP, SIGTERM, WCONTINUED, WUNTRACED,
-};
+use libc::{fork, kill, waitpid, SIGCONT, SIGSTOP, SIGTERM, WCONTINUED, WUNTRACED};
 use test_utils::*;
 
 #[test]
@@ -29,7 +27,8 @@ fn test_sigstop_sigcont_behavior() {
             // Parent process
             let mut status: i32 = 0;
 
-            // 1. Send SIGSTOP and wait for the child to stop
+            // 1. Send SIGSTOP to the running child and wait for it to stop
+            assert_alive(pid);
             let kill_res = kill(pid, SIGSTOP);
             assert_eq!(kill_res, 0, "kill(SIGSTOP) 应成功");
 
@@ -50,7 +49,8 @@ fn test_sigstop_sigcont_behavior() {
             // 4. Verify the child has continued
             assert!(wifcontinued!(status), "子进程应处于继续运行状态");
 
-            // 5. Terminate the child for cleanup
+            // 5. Terminate the (running again) child for cleanup
+            assert_alive(pid);
             let kill_res_term = kill(pid, SIGTERM);
             assert_eq!(kill_res_term, 0, "kill(SIGTERM) 应成功");
 
@@ -61,10 +61,7 @@ fn test_sigstop_sigcont_behavior() {
             assert!(!wifexited!(status), "子进程不应正常退出");
 
             // 7. Final wait to ensure no more child processes are left
-            let wait_res_final = waitpid(pid, &mut status, 0);
-            assert_eq!(wait_res_final, -1, "第二次 waitpid 应失败");
-            let errno = *libc::__errno_location();
-            assert_eq!(errno, ECHILD, "回收子进程后 errno 应为 ECHILD");
+            assert_no_more_children(pid);
         }
     }
 }
@@ -7,13 +7,18 @@
 //! - WIFCONTINUED: Continue detection
 //! - WCOREDUMP: Core dump detection
 //! - Zombie process handling
+//! - WNOWAIT: Observing an exit status without reaping the child
 //! - Job control signals (SIGSTOP, SIGCONT, SIGTSTP, SIGTTIN, SIGTTOU)
+//!
+//! Most cases fork plain children that share the test's session and process
+//! group. `waitpid_wuntraced_sigtstp_in_new_session` sets up a session with
+//! `fork_session` and stops a job in its own group, the way a shell does.
 
 use libc::{
-    exit, fork, kill, raise, waitpid, ECHILD, SIGCONT, SIGSTOP, SIGTERM, SIGTSTP, WCONTINUED,
-    WNOHANG, WUNTRACED,
+    exit, fork, getpid, getsid, kill, raise, setpgid, waitpid, EINVAL, SIGCONT, SIGKILL, SIGSTOP,
+    SIGTERM, SIGTSTP, WCONTINUED, WEXITED, WNOHANG, WNOWAIT, WSTOPPED, WUNTRACED,
 };
-use std::{ptr, time::Duration, thread::sleep};
+use std::{ptr, time::Duration};
 use test_utils::*; // Import status macros
 
 #[test]
@@ -76,6 +81,46 @@ fn waitpid_wuntraced_sigtstp() {
     }
 }
 
+#[test]
+fn waitpid_wuntraced_sigtstp_in_new_session() {
+    // A session leader acting as the shell: its own group is orphaned, so the
+    // stop has to happen in a job (process group) it created.
+    let leader = fork_session(|| unsafe {
+        assert_eq!(getsid(0), getpid(), "子进程应为新会话的首进程");
+        let job = fork();
+        assert!(job >= 0, "fork 失败");
+        if job == 0 {
+            setpgid(0, 0);
+            raise(SIGTSTP);
+            exit(0);
+        }
+        let mut status: i32 = 0;
+        assert_eq!(
+            waitpid(job, &mut status, WUNTRACED),
+            job,
+            "waitpid 应返回作业进程 PID"
+        );
+        assert!(wifstopped!(status), "新进程组中的作业应被 SIGTSTP 停止");
+        assert_eq!(wstopsig!(status), SIGTSTP, "停止信号应为 SIGTSTP");
+        kill(job, SIGCONT);
+        assert_eq!(
+            waitpid(job, &mut status, 0),
+            job,
+            "waitpid 应返回作业进程 PID"
+        );
+        assert_exited(status, 0);
+    })
+    .expect("fork_session 失败");
+
+    let mut status: i32 = 0;
+    assert_eq!(
+        unsafe { waitpid(leader, &mut status, 0) },
+        leader,
+        "waitpid 应返回会话首进程 PID"
+    );
+    assert_exited(status, 0);
+}
+
 #[test]
 fn waitpid_without_wuntraced_doesnt_return() {
     // Test that without WUNTRACED, waitpid doesn't return for stopped children
@@ -88,8 +133,24 @@ fn waitpid_without_wuntraced_doesnt_return() {
             raise(SIGSTOP);
             exit(42);
         } else {
-            // Parent process - use WNOHANG without WUNTRACED
-            libc::usleep(100_000); // Give child time to stop
+            // Parent process - block until the child has stopped without
+            // consuming the stop
+            let mut info: libc::siginfo_t = std::mem::zeroed();
+            let stopped = libc::waitid(
+                libc::P_PID,
+                pid as libc::id_t,
+                &mut info,
+                WSTOPPED | WNOWAIT,
+            );
+            if stopped != 0 {
+                println!("SKIP: waitid 不支持 WSTOPPED | WNOWAIT");
+                let mut status: i32 = 0;
+                kill(pid, SIGKILL);
+                waitpid(pid, &mut status, 0);
+                return;
+            }
+
+            // Use WNOHANG without WUNTRACED
 
             let mut status: i32 = 0;
             let result = waitpid(pid, &mut status, WNOHANG);
@@ -167,27 +228,24 @@ fn waitpid_wcontinued_with_wnohang() {
             // but this is a soft check (implementation may vary)
             let result = waitpid(pid, &mut status, WCONTINUED | WNOHANG);
             if result != 0 {
-                eprintln!(
-                    "Warning: WCONTINUED before SIGCONT returned {} (expected 0)",
-                    result
-                );
+                eprintln!("Warning: WCONTINUED before SIGCONT returned {result} (expected 0)");
             }
 
             // Now continue the child
             assert_eq!(kill(pid, SIGCONT), 0, "发送 SIGCONT 应成功");
-            sleep(Duration::from_millis(50));
 
             // Should now detect the continue - this is the critical check
-            let result2 = waitpid(pid, &mut status, WCONTINUED | WNOHANG);
+            let (result2, mut status) =
+                wait_for_state_change(pid, WCONTINUED, Duration::from_secs(1))
+                    .expect("等待继续事件失败")
+                    .expect("1 秒内应检测到继续事件");
             assert_eq!(
                 result2, pid,
-                "应检测到继续事件 (result={}, status=0x{:x})",
-                result2, status
+                "应检测到继续事件 (result={result2}, status=0x{status:x})"
             );
             assert!(
                 wifcontinued!(status),
-                "状态应指示继续 (expected 0xffff, got 0x{:x})",
-                status
+                "状态应指示继续 (expected 0xffff, got 0x{status:x})"
             );
 
             // Clean up
@@ -208,11 +266,10 @@ fn waitpid_stop_continue_exit_sequence() {
             raise(SIGSTOP);
             exit(99);
         } else {
-            // Parent process
-            let mut status: i32 = 0;
+            // Parent process - every wait tolerates EINTR from signal traffic
 
             // 1. Wait for stop
-            let r1 = waitpid(pid, &mut status, WUNTRACED);
+            let (r1, status) = wait_for_eintr_safe(pid, WUNTRACED).expect("等待停止失败");
             assert_eq!(r1, pid, "应检测到停止");
             assert!(wifstopped!(status), "应处于停止状态");
             assert_eq!(wstopsig!(status), SIGSTOP, "停止信号应为 SIGSTOP");
@@ -221,15 +278,14 @@ fn waitpid_stop_continue_exit_sequence() {
             kill(pid, SIGCONT);
 
             // 3. Wait for continue event
-            let r2 = waitpid(pid, &mut status, WCONTINUED);
+            let (r2, status) = wait_for_eintr_safe(pid, WCONTINUED).expect("等待继续失败");
             assert_eq!(r2, pid, "应检测到继续");
             assert!(wifcontinued!(status), "应处于继续状态");
 
             // 4. Wait for exit
-            let r3 = waitpid(pid, &mut status, 0);
+            let (r3, status) = wait_for_eintr_safe(pid, 0).expect("等待退出失败");
             assert_eq!(r3, pid, "应检测到退出");
-            assert!(wifexited!(status), "应正常退出");
-            assert_eq!(wexitstatus!(status), 99, "退出码应为 99");
+            assert_exited(status, 99);
         }
     }
 }
@@ -249,27 +305,27 @@ fn waitpid_multiple_stops_and_continues() {
             }
             exit(0);
         } else {
-            // Parent process - stop and continue multiple times
+            // Parent process - stop and continue multiple times; each wait
+            // retries on EINTR instead of assuming the first call returns the child
             for i in 0..3 {
-                let mut status: i32 = 0;
-
                 // Wait for stop
-                let r1 = waitpid(pid, &mut status, WUNTRACED);
-                assert_eq!(r1, pid, "第 {} 次停止检测失败", i);
-                assert!(wifstopped!(status), "第 {} 次应处于停止状态", i);
+                let (r1, status) = wait_for_eintr_safe(pid, WUNTRACED)
+                    .unwrap_or_else(|err| panic!("第 {i} 次等待停止失败: {err}"));
+                assert_eq!(r1, pid, "第 {i} 次停止检测失败");
+                assert!(wifstopped!(status), "第 {i} 次应处于停止状态");
 
                 // Continue
                 kill(pid, SIGCONT);
 
                 // Optionally wait for continue event
-                let r2 = waitpid(pid, &mut status, WCONTINUED);
-                assert_eq!(r2, pid, "第 {} 次继续检测失败", i);
-                assert!(wifcontinued!(status), "第 {} 次应处于继续状态", i);
+                let (r2, status) = wait_for_eintr_safe(pid, WCONTINUED)
+                    .unwrap_or_else(|err| panic!("第 {i} 次等待继续失败: {err}"));
+                assert_eq!(r2, pid, "第 {i} 次继续检测失败");
+                assert!(wifcontinued!(status), "第 {i} 次应处于继续状态");
             }
 
             // Wait for final exit
-            let mut status: i32 = 0;
-            let r_final = waitpid(pid, &mut status, 0);
+            let (r_final, status) = wait_for_eintr_safe(pid, 0).expect("等待最终退出失败");
             assert_eq!(r_final, pid, "最终退出检测失败");
             assert!(wifexited!(status), "应正常退出");
         }
@@ -302,7 +358,7 @@ fn waitpid_all_status_macros() {
             libc::sleep(10);
             exit(0);
         } else {
-            libc::usleep(50_000);
+            assert_alive(pid2);
             kill(pid2, SIGTERM);
             let mut status: i32 = 0;
             waitpid(pid2, &mut status, 0);
@@ -352,14 +408,10 @@ fn waitpid_zombie_no_hang() {
             let result = waitpid(pid, &mut status, WNOHANG);
 
             assert_eq!(result, pid, "僵尸进程应立即被回收");
-            assert!(wifexited!(status), "应正常退出");
-            assert_eq!(wexitstatus!(status), 55, "退出码应为 55");
+            assert_exited(status, 55);
 
             // Try to wait again - should get ECHILD
-            let result2 = waitpid(pid, &mut status, 0);
-            assert_eq!(result2, -1, "再次等待应失败");
-            let errno = *libc::__errno_location();
-            assert_eq!(errno, ECHILD, "errno 应为 ECHILD");
+            assert_no_more_children(pid);
         }
     }
 }
@@ -376,38 +428,31 @@ fn waitpid_combined_flags() {
             raise(SIGSTOP);
             exit(0);
         } else {
-            // Parent process
-            let combined_flags = WUNTRACED | WCONTINUED | WNOHANG;
-            let mut status: i32 = 0;
+            // Parent process; wait_for_state_change adds WNOHANG to every poll
+            let combined_flags = WUNTRACED | WCONTINUED;
+            let timeout = Duration::from_millis(500);
 
             // Poll until we detect the stop
-            let mut detected = false;
-            for _ in 0..10 {
-                let result = waitpid(pid, &mut status, combined_flags);
-                if result == pid && wifstopped!(status) {
-                    detected = true;
-                    break;
-                }
-                libc::usleep(50_000);
-            }
-            assert!(detected, "应检测到停止事件");
+            let stopped =
+                wait_for_state_change(pid, combined_flags, timeout).expect("等待停止事件失败");
+            assert!(
+                matches!(stopped, Some((ret, status)) if ret == pid && wifstopped!(status)),
+                "应检测到停止事件 (got {stopped:?})"
+            );
 
             // Continue the child
             kill(pid, SIGCONT);
 
             // Poll until we detect the continue
-            detected = false;
-            for _ in 0..10 {
-                let result = waitpid(pid, &mut status, combined_flags);
-                if result == pid && wifcontinued!(status) {
-                    detected = true;
-                    break;
-                }
-                libc::usleep(50_000);
-            }
-            assert!(detected, "应检测到继续事件");
+            let continued =
+                wait_for_state_change(pid, combined_flags, timeout).expect("等待继续事件失败");
+            assert!(
+                matches!(continued, Some((ret, status)) if ret == pid && wifcontinued!(status)),
+                "应检测到继续事件 (got {continued:?})"
+            );
 
             // Wait for exit
+            let mut status: i32 = 0;
             waitpid(pid, &mut status, 0);
         }
     }
@@ -504,4 +549,70 @@ fn waitpid_linux_abi_smoke() {
             assert!(wifexited!(status), "子进程应正常退出");
         }
     }
-}
\ No newline at end of file
+}
+
+#[test]
+fn waitpid_wnowait_status_observable_twice() {
+    // Test WNOWAIT leaves the child waitable so the status can be read again
+    unsafe {
+        let pid = fork();
+        assert!(pid >= 0, "fork 失败");
+
+        if pid == 0 {
+            exit(7);
+        } else {
+            let mut peeked: i32 = 0;
+            let result = waitpid(pid, &mut peeked, WEXITED | WNOWAIT);
+            if result == -1 && std::io::Error::last_os_error().raw_os_error() == Some(EINVAL) {
+                // waitpid 不支持 WNOWAIT（Linux 的 wait4 即如此），回收子进程后跳过
+                println!("SKIP: waitpid 不支持 WNOWAIT (EINVAL)");
+                let mut status: i32 = 0;
+                waitpid(pid, &mut status, 0);
+                return;
+            }
+            assert_eq!(result, pid, "WNOWAIT 的 waitpid 应返回子进程 PID");
+            assert_exited(peeked, 7);
+
+            // The child must still be waitable with the same status
+            let mut status: i32 = 0;
+            let result2 = waitpid(pid, &mut status, 0);
+            assert_eq!(result2, pid, "WNOWAIT 之后子进程应仍可被回收");
+            assert_eq!(status, peeked, "WNOWAIT 观察到的状态应与 waitpid 一致");
+
+            assert_no_more_children(pid);
+        }
+    }
+}
+
+#[test]
+fn peek_status_leaves_child_waitable() {
+    // Test the peek_status helper (waitid + WNOWAIT) against a real wait
+    unsafe {
+        let pid = fork();
+        assert!(pid >= 0, "fork 失败");
+
+        if pid == 0 {
+            raise(SIGTERM);
+            exit(0);
+        } else {
+            let peeked = match peek_status(pid).expect("peek_status 失败") {
+                Some(status) => status,
+                None => {
+                    println!("SKIP: waitid 不支持 WNOWAIT (EINVAL)");
+                    let mut status: i32 = 0;
+                    waitpid(pid, &mut status, 0);
+                    return;
+                }
+            };
+            assert_signaled(peeked, SIGTERM);
+
+            // Peeking twice observes the same state
+            assert_eq!(peek_status(pid).expect("peek_status 失败"), Some(peeked));
+
+            let mut status: i32 = 0;
+            let result = waitpid(pid, &mut status, 0);
+            assert_eq!(result, pid, "peek 之后子进程应仍可被回收");
+            assert_eq!(status, peeked, "peek 到的状态应与 waitpid 一致");
+        }
+    }
+}
@@ -32,8 +32,7 @@ fn waitpid_basic_child_exit() {
             let mut status: i32 = 0;
             let result = waitpid(pid, &mut status, 0);
             assert_eq!(result, pid, "waitpid 应返回子进程 PID");
-            assert!(wifexited!(status), "子进程应正常退出");
-            assert_eq!(wexitstatus!(status), 0, "退出码应为 0");
+            assert_exited(status, 0);
         }
     }
 }
@@ -56,13 +55,7 @@ fn waitpid_child_exit_with_code() {
                 let mut status: i32 = 0;
                 let result = waitpid(pid, &mut status, 0);
                 assert_eq!(result, pid, "waitpid 应返回子进程 PID");
-                assert!(wifexited!(status), "子进程应正常退出");
-                assert_eq!(
-                    wexitstatus!(status),
-                    exit_code,
-                    "退出码应为 {}",
-                    exit_code
-                );
+                assert_exited(status, exit_code);
             }
         }
     }
@@ -83,8 +76,7 @@ fn waitpid_any_child() {
             let mut status: i32 = 0;
             let result = waitpid(-1, &mut status, 0);
             assert_eq!(result, pid, "waitpid(-1) 应返回子进程 PID");
-            assert!(wifexited!(status), "子进程应正常退出");
-            assert_eq!(wexitstatus!(status), 17, "退出码应为 17");
+            assert_exited(status, 17);
         }
     }
 }
@@ -137,8 +129,7 @@ fn waitpid_wnohang_child_ready() {
             let mut status: i32 = 0;
             let result = waitpid(pid, &mut status, WNOHANG);
             assert_eq!(result, pid, "waitpid 应返回子进程 PID");
-            assert!(wifexited!(status), "子进程应正常退出");
-            assert_eq!(wexitstatus!(status), 33, "退出码应为 33");
+            assert_exited(status, 33);
         }
     }
 }
@@ -192,87 +183,118 @@ fn waitpid_invalid_options() {
 #[test]
 fn waitpid_multiple_children_sequential() {
     // Test waiting for multiple children sequentially
-    unsafe {
-        let child_count = 5;
-        let mut children = Vec::new();
-
-        // Fork multiple children
-        for i in 0..child_count {
-            let pid = fork();
-            assert!(pid >= 0, "fork 失败");
-
-            if pid == 0 {
-                // Child process - exit with unique code
-                exit(i + 10);
-            } else {
-                // Parent process - record child PID
-                children.push(pid);
-            }
-        }
-
-        // Parent process - wait for all children
-        let mut exit_codes = Vec::new();
-        for &child_pid in &children {
-            let mut status: i32 = 0;
-            let result = waitpid(child_pid, &mut status, 0);
-            assert_eq!(result, child_pid, "waitpid 应返回对应的子进程 PID");
-            assert!(wifexited!(status), "子进程应正常退出");
-            exit_codes.push(wexitstatus!(status));
-        }
-
-        // Verify all exit codes
-        assert_eq!(exit_codes.len(), child_count as usize, "应收集所有退出码");
-        for code in &exit_codes {
-            assert!(
-                *code >= 10 && *code < 10 + child_count,
-                "退出码应在 10-{} 范围内",
-                10 + child_count
-            );
-        }
+    let child_count = 5;
+    // Child process - exit with unique code
+    let children = spawn_children(child_count, |i| unsafe { exit(i as i32 + 10) });
+
+    // Parent process - wait for all children
+    let reaped = reap_all(&children).expect("回收子进程失败");
+    assert_eq!(reaped.len(), child_count, "应收集所有退出码");
+    for (i, &(pid, status)) in reaped.iter().enumerate() {
+        assert_eq!(pid, children[i], "waitpid 应返回对应的子进程 PID");
+        assert_exited(status, i as i32 + 10);
     }
 }
 
 #[test]
 fn waitpid_multiple_children_any_order() {
     // Test waiting for any child with multiple children
-    unsafe {
-        let child_count = 3;
-        let mut expected_pids = Vec::new();
+    let child_count = 3;
+    // Child process - exit with unique code
+    let expected_pids = spawn_children(child_count, |i| unsafe { exit(i as i32 + 20) });
+
+    // Parent process - wait for all children using -1
+    let mut reaped_pids = Vec::new();
+    for _ in 0..child_count {
+        let (result, status) = wait_for_eintr_safe(-1, 0).expect("waitpid(-1) 失败");
+        assert!(result > 0, "waitpid(-1) 应返回有效 PID");
+        assert!(wifexited!(status), "子进程应正常退出");
+        reaped_pids.push(result);
+    }
 
-        // Fork multiple children
-        for i in 0..child_count {
-            let pid = fork();
-            assert!(pid >= 0, "fork 失败");
+    // Verify all children were reaped
+    assert_eq!(reaped_pids.len(), child_count, "应回收所有子进程");
+    for &pid in &expected_pids {
+        assert!(reaped_pids.contains(&pid), "应回收 PID {pid} 的子进程");
+    }
+}
 
-            if pid == 0 {
-                // Child process - exit with unique code
-                exit(20 + i);
-            } else {
-                // Parent process - record child PID
-                expected_pids.push(pid);
+#[test]
+fn reap_remaining_collects_exited_children() {
+    // Run inside a child of our own so waitpid(-1) cannot steal the children
+    // of tests running on other threads
+    let sandbox = spawn_children(1, |_| {
+        let children = spawn_children(3, |i| unsafe { exit(i as i32 + 30) });
+        for &pid in &children {
+            // Block until the child has exited without reaping it
+            if peek_status(pid).expect("查看子进程状态失败").is_none() {
+                unsafe { libc::usleep(100_000) };
             }
         }
 
-        // Parent process - wait for all children using -1
-        let mut reaped_pids = Vec::new();
-        for _ in 0..child_count {
-            let mut status: i32 = 0;
-            let result = waitpid(-1, &mut status, 0);
-            assert!(result > 0, "waitpid(-1) 应返回有效 PID");
-            assert!(wifexited!(status), "子进程应正常退出");
-            reaped_pids.push(result);
+        let reaped = reap_remaining();
+        assert_eq!(
+            reaped.len(),
+            children.len(),
+            "应回收所有已退出的子进程: {reaped:?}"
+        );
+        for (pid, status) in reaped {
+            let idx = children
+                .iter()
+                .position(|&child| child == pid)
+                .unwrap_or_else(|| panic!("回收到了意外的子进程 {pid}"));
+            assert_exited(status, idx as i32 + 30);
         }
-
-        // Verify all children were reaped
-        assert_eq!(reaped_pids.len(), child_count as usize, "应回收所有子进程");
-        for &pid in &expected_pids {
-            assert!(
-                reaped_pids.contains(&pid),
-                "应回收 PID {} 的子进程",
-                pid
-            );
+        assert!(
+            reap_remaining().is_empty(),
+            "再次调用时不应回收到任何子进程"
+        );
+        for &pid in &children {
+            assert_reaped(pid);
         }
-    }
+    });
+
+    let reaped = reap_all(&sandbox).expect("回收子进程失败");
+    let (_, status) = reaped[0];
+    assert!(
+        wifexited!(status) && wexitstatus!(status) == 0,
+        "子进程内的断言失败 (status=0x{status:x})"
+    );
+}
+
+#[test]
+fn waitpid_interrupted_by_alarm_returns_eintr() {
+    // SIGALRM must reach the thread blocked in waitpid, so use a
+    // single-threaded child of our own
+    let sandbox = spawn_children(1, |_| {
+        let child = spawn_children(1, |_| unsafe {
+            libc::pause();
+        })[0];
+        let (ret, err) = run_with_alarm(1, || {
+            let mut status = 0;
+            let ret = unsafe { waitpid(child, &mut status, 0) };
+            (ret, std::io::Error::last_os_error())
+        });
+        assert_eq!(ret, -1, "waitpid 应被 SIGALRM 打断");
+        assert_eq!(
+            err.raw_os_error(),
+            Some(libc::EINTR),
+            "waitpid 应返回 EINTR"
+        );
+        assert_alive(child);
+
+        unsafe { kill(child, SIGKILL) };
+        let reaped = reap_all(&[child]).expect("回收子进程失败");
+        let (_, status) = reaped[0];
+        assert_signaled(status, SIGKILL);
+    });
+
+    let reaped = reap_all(&sandbox).expect("回收子进程失败");
+    let (_, status) = reaped[0];
+    assert!(
+        wifexited!(status) && wexitstatus!(status) == 0,
+        "子进程内的断言失败 (status=0x{status:x})"
+    );
 }
 
 #[test]
@@ -312,11 +334,7 @@ fn waitpid_twice_returns_error() {
             assert!(wifexited!(status), "子进程应正常退出");
 
             // Try to wait again - should fail with ECHILD
-            let result2 = waitpid(pid, &mut status, 0);
-            assert_eq!(result2, -1, "第二次 waitpid 应失败");
-
-            let errno = *libc::__errno_location();
-            assert_eq!(errno, ECHILD, "errno 应为 ECHILD");
+            assert_no_more_children(pid);
         }
     }
 }
@@ -341,8 +359,7 @@ fn waitpid_process_group_zero() {
 
             // Should wait for any child in same group (which includes our child)
             assert_eq!(result, pid, "waitpid(0) 应返回子进程 PID");
-            assert!(wifexited!(status), "子进程应正常退出");
-            assert_eq!(wexitstatus!(status), 88, "退出码应为 88");
+            assert_exited(status, 88);
         }
     }
 }
@@ -398,8 +415,8 @@ fn waitpid_signal_termination() {
             libc::sleep(10);
             exit(0); // Should never reach here
         } else {
-            // Parent process - give child time to start, then kill it
-            libc::usleep(100_000); // 100ms
+            // Parent process - make sure the child is running, then kill it
+            assert_alive(pid);
 
             let kill_result = kill(pid, SIGTERM);
             assert_eq!(kill_result, 0, "kill 应成功");
@@ -407,10 +424,10 @@ fn waitpid_signal_termination() {
             let mut status: i32 = 0;
             let result = waitpid(pid, &mut status, 0);
             assert_eq!(result, pid, "waitpid 应返回子进程 PID");
+            assert_reaped(pid);
 
             // Child should be terminated by signal
-            assert!(wifsignaled!(status), "子进程应被信号终止");
-            assert_eq!(wtermsig!(status), SIGTERM, "终止信号应为 SIGTERM");
+            assert_signaled(status, SIGTERM);
         }
     }
 }
@@ -428,7 +445,7 @@ fn waitpid_sigkill() {
             exit(0);
         } else {
             // Parent process - kill with SIGKILL
-            libc::usleep(100_000); // 100ms
+            assert_alive(pid);
 
             let kill_result = kill(pid, SIGKILL);
             assert_eq!(kill_result, 0, "kill 应成功");
@@ -436,9 +453,9 @@ fn waitpid_sigkill() {
             let mut status: i32 = 0;
             let result = waitpid(pid, &mut status, 0);
             assert_eq!(result, pid, "waitpid 应返回子进程 PID");
+            assert_reaped(pid);
 
-            assert!(wifsignaled!(status), "子进程应被信号终止");
-            assert_eq!(wtermsig!(status), SIGKILL, "终止信号应为 SIGKILL");
+            assert_signaled(status, SIGKILL);
         }
     }
 }
@@ -458,8 +475,7 @@ fn waitpid_smoke() {
             let mut status: i32 = 0;
             let result = waitpid(pid, &mut status, 0);
             assert_eq!(result, pid, "waitpid 应返回子进程 PID");
-            assert!(wifexited!(status), "子进程应正常退出");
-            assert_eq!(wexitstatus!(status), 0, "退出码应为 0");
+            assert_exited(status, 0);
         }
     }
 }
@@ -1,4 +1,7 @@
-use test_utils::{append_bytes, cleanup_file, read_bytes, temp_file, write_bytes};
+use test_utils::{
+    append_bytes, assert_files_equal, cleanup_file, random_bytes, read_bytes, read_bytes_limited,
+    read_bytes_limited_with, temp_file, write_bytes, Oversize,
+};
 
 #[test]
 fn file_io_basic_smoke() {
@@ -17,3 +20,41 @@ fn file_io_basic_smoke() {
 
     assert_eq!(read_back, expected, "读回内容不一致");
 }
+
+#[test]
+fn file_io_copy_round_trip() {
+    let source = temp_file("starry-file-io-src", false).expect("生成源文件路径");
+    let copy = temp_file("starry-file-io-copy", false).expect("生成副本路径");
+    // 跨越多个读取块，覆盖分块比较的边界
+    let payload = random_bytes(20_000);
+    write_bytes(&source, &payload).expect("写入源文件");
+    std::fs::copy(&source, &copy).expect("复制文件");
+
+    let same = assert_files_equal(&source, &copy);
+    write_bytes(&copy, &payload[..payload.len() - 1]).expect("截断副本");
+    let shorter = assert_files_equal(&source, &copy);
+    cleanup_file(&source).expect("清理源文件");
+    cleanup_file(&copy).expect("清理副本");
+
+    same.expect("副本内容应与源文件一致");
+    let err = shorter.expect_err("长度不同的文件应比较失败");
+    assert!(
+        err.to_string().contains("长度不同"),
+        "错误信息应说明长度不同: {err}"
+    );
+}
+
+#[test]
+fn file_io_read_limited() {
+    let file_path = temp_file("starry-file-io-limit", false).expect("生成临时文件路径");
+    write_bytes(&file_path, b"0123456789").expect("写入内容");
+
+    let exact = read_bytes_limited(&file_path, 10);
+    let over = read_bytes_limited(&file_path, 4);
+    let truncated = read_bytes_limited_with(&file_path, 4, Oversize::Truncate);
+    cleanup_file(&file_path).expect("清理临时文件");
+
+    assert_eq!(exact.expect("恰好等于上限应能读取"), b"0123456789");
+    assert!(over.is_err(), "超过上限应返回错误");
+    assert_eq!(truncated.expect("截断模式应成功"), b"0123");
+}
@@ -15,10 +15,7 @@ fn multi_processors_smoke() {
     let t1 = Instant::now();
     let single_result = compute_range_sum(0, n);
     let single_time = t1.elapsed();
-    println!(
-        "Single-thread result = {single_result}, time = {:?}",
-        single_time
-    );
+    println!("Single-thread result = {single_result}, time = {single_time:?}");
 
     println!("\nRunning multi-threaded computation...");
     println!("Using {PROCESSORS} threads");
@@ -47,10 +44,7 @@ fn multi_processors_smoke() {
     }
 
     let multi_time = t2.elapsed();
-    println!(
-        "Multi-thread result = {multi_result}, time = {:?}",
-        multi_time
-    );
+    println!("Multi-thread result = {multi_result}, time = {multi_time:?}");
 
     // === Validate ===
     assert_eq!(single_result, multi_result, "Results do not match!");
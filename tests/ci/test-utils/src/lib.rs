@@ -4,9 +4,10 @@ use anyhow::{anyhow, Context, Result};
 use rand::{distributions::Alphanumeric, Rng};
 use std::{
     fs::{self, File, OpenOptions},
-    io::{Read, Write},
+    io::{BufReader, Read, Write},
     path::{Path, PathBuf},
     process::{Command, ExitStatus, Stdio},
+    time::{Duration, Instant},
 };
 
 /// 生成一个位于系统临时目录下的唯一文件路径。
@@ -18,7 +19,7 @@ pub fn temp_file(prefix: &str, create: bool) -> Result<PathBuf> {
         .take(8)
         .map(char::from)
         .collect();
-    let filename = format!("{}-{}", prefix, suffix);
+    let filename = format!("{prefix}-{suffix}");
     path.push(filename);
     if create {
         File::create(&path).with_context(|| format!("无法创建临时文件 {}", path.display()))?;
@@ -44,7 +45,8 @@ pub fn append_bytes<P: AsRef<Path>>(path: P, data: &[u8]) -> Result<()> {
         .with_context(|| format!("刷新写入失败: {}", path.as_ref().display()))
 }
 
-/// 读取文件全部内容。
+/// 读取文件全部内容。仅用于大小已知的小文件；
+/// 大小不可控（例如由被测用例生成）的文件请用 [`read_bytes_limited`]。
 pub fn read_bytes<P: AsRef<Path>>(path: P) -> Result<Vec<u8>> {
     let mut buffer = Vec::new();
     let mut file = OpenOptions::new()
@@ -56,6 +58,128 @@ pub fn read_bytes<P: AsRef<Path>>(path: P) -> Result<Vec<u8>> {
     Ok(buffer)
 }
 
+/// 文件超过 [`read_bytes_limited_with`] 的上限时的处理方式。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Oversize {
+    /// 返回错误，不返回任何内容。
+    Error,
+    /// 只返回前 `max` 个字节。
+    Truncate,
+}
+
+/// 读取文件内容，但最多读取 `max` 字节；文件更大时返回错误。
+/// 防止用例意外生成的超大文件耗尽测试进程的内存。
+pub fn read_bytes_limited<P: AsRef<Path>>(path: P, max: usize) -> Result<Vec<u8>> {
+    read_bytes_limited_with(path, max, Oversize::Error)
+}
+
+/// 同 [`read_bytes_limited`]，由 `oversize` 决定超出上限时报错还是截断。
+pub fn read_bytes_limited_with<P: AsRef<Path>>(
+    path: P,
+    max: usize,
+    oversize: Oversize,
+) -> Result<Vec<u8>> {
+    let path = path.as_ref();
+    let file = File::open(path).with_context(|| format!("打开文件失败: {}", path.display()))?;
+    let mut buffer = Vec::new();
+    // 多读一个字节，用于判断文件是否超过上限
+    file.take(max as u64 + 1)
+        .read_to_end(&mut buffer)
+        .with_context(|| format!("读取文件失败: {}", path.display()))?;
+    if buffer.len() > max {
+        if oversize == Oversize::Error {
+            return Err(anyhow!("文件 {} 超过读取上限 {max} 字节", path.display()));
+        }
+        buffer.truncate(max);
+    }
+    Ok(buffer)
+}
+
+/// 逐块比较两个文件的内容，相同时返回 `Ok(())`。
+/// 不同时错误信息给出第一个不同字节的偏移及其前后的字节（十六进制）；
+/// 长度不同时单独说明两边的长度以及相同前缀的长度。两个文件都不会整体读入内存。
+pub fn assert_files_equal<P: AsRef<Path>, Q: AsRef<Path>>(a: P, b: Q) -> Result<()> {
+    const CHUNK: usize = 8192;
+    const CONTEXT: usize = 8;
+    let (a, b) = (a.as_ref(), b.as_ref());
+    let open = |path: &Path| {
+        File::open(path)
+            .map(BufReader::new)
+            .with_context(|| format!("打开文件失败: {}", path.display()))
+    };
+    let (mut reader_a, mut reader_b) = (open(a)?, open(b)?);
+    let (mut buf_a, mut buf_b) = (vec![0u8; CHUNK], vec![0u8; CHUNK]);
+    // 上一块末尾的相同字节，用于展示跨块边界的上下文
+    let mut previous: Vec<u8> = Vec::new();
+    let mut offset = 0u64;
+    loop {
+        let len_a = read_full(&mut reader_a, &mut buf_a)
+            .with_context(|| format!("读取文件失败: {}", a.display()))?;
+        let len_b = read_full(&mut reader_b, &mut buf_b)
+            .with_context(|| format!("读取文件失败: {}", b.display()))?;
+        let common = len_a.min(len_b);
+        if let Some(idx) = (0..common).find(|&idx| buf_a[idx] != buf_b[idx]) {
+            let mut before = previous.clone();
+            before.extend_from_slice(&buf_a[..idx]);
+            let before = &before[before.len().saturating_sub(CONTEXT)..];
+            return Err(anyhow!(
+                "文件内容不同: {} 与 {} 在偏移 {} 处首次不同\n  之前: {}\n  {}: {}\n  {}: {}",
+                a.display(),
+                b.display(),
+                offset + idx as u64,
+                hex_bytes(before),
+                a.display(),
+                hex_bytes(&buf_a[idx..len_a.min(idx + CONTEXT)]),
+                b.display(),
+                hex_bytes(&buf_b[idx..len_b.min(idx + CONTEXT)]),
+            ));
+        }
+        if len_a != len_b {
+            let size = |path: &Path| {
+                fs::metadata(path).map_or_else(|_| "?".to_string(), |meta| meta.len().to_string())
+            };
+            return Err(anyhow!(
+                "文件长度不同: {} 有 {} 字节，{} 有 {} 字节（前 {} 字节相同）",
+                a.display(),
+                size(a),
+                b.display(),
+                size(b),
+                offset + common as u64
+            ));
+        }
+        if len_a == 0 {
+            return Ok(());
+        }
+        offset += len_a as u64;
+        previous = buf_a[len_a.saturating_sub(CONTEXT)..len_a].to_vec();
+    }
+}
+
+/// 尽量填满 `buf`，只有到达文件末尾时才返回不足一块的长度。
+fn read_full<R: Read>(reader: &mut R, buf: &mut [u8]) -> std::io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(len) => filled += len,
+            Err(err) if err.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(filled)
+}
+
+fn hex_bytes(bytes: &[u8]) -> String {
+    if bytes.is_empty() {
+        return "(无)".to_string();
+    }
+    bytes
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
 /// 删除文件，忽略不存在的情况。
 pub fn cleanup_file<P: AsRef<Path>>(path: P) -> Result<()> {
     match fs::remove_file(&path) {
@@ -85,6 +209,374 @@ pub fn ensure_syscall_success(ret: i64, context: &str) -> Result<i64> {
     }
 }
 
+/// 调用 `waitpid`，遇到 `EINTR` 时自动重试。
+/// 成功时返回 `(waitpid 返回值, status)`；其他错误原样返回。
+pub fn wait_for_eintr_safe(
+    pid: libc::pid_t,
+    options: libc::c_int,
+) -> Result<(libc::pid_t, libc::c_int)> {
+    loop {
+        let mut status: libc::c_int = 0;
+        let ret = unsafe { libc::waitpid(pid, &mut status, options) };
+        if ret >= 0 {
+            return Ok((ret, status));
+        }
+        let err = std::io::Error::last_os_error();
+        if err.raw_os_error() != Some(libc::EINTR) {
+            return Err(anyhow!("waitpid({pid}, {options:#x}) 失败: {err}"));
+        }
+    }
+}
+
+/// [`run_with_alarm`] 安装的 SIGALRM 处理函数：什么也不做，只为打断阻塞中的系统调用。
+extern "C" fn ignore_alarm(_: libc::c_int) {}
+
+/// 恢复 [`run_with_alarm`] 之前的 SIGALRM 处理方式，`f` panic 时同样生效。
+struct AlarmGuard(libc::sigaction);
+
+impl Drop for AlarmGuard {
+    fn drop(&mut self) {
+        unsafe {
+            libc::alarm(0);
+            libc::sigaction(libc::SIGALRM, &self.0, std::ptr::null_mut());
+        }
+    }
+}
+
+/// 以 `sigaction` 安装不带 `SA_RESTART` 的 SIGALRM 处理函数并 `alarm(secs)`，然后执行 `f`。
+/// `f` 中阻塞的 `waitpid`/`read` 等会在 `secs` 秒后被信号打断并返回 `EINTR`。
+/// 返回（或 panic）时取消尚未触发的闹钟并恢复原有的处理方式。
+/// SIGALRM 可能投递给进程中的任意线程，测试框架本身是多线程的，因此请在
+/// [`spawn_children`] 创建的单线程子进程中调用。
+pub fn run_with_alarm<T, F: FnOnce() -> T>(secs: u32, f: F) -> T {
+    let mut action: libc::sigaction = unsafe { std::mem::zeroed() };
+    action.sa_sigaction = ignore_alarm as extern "C" fn(libc::c_int) as libc::sighandler_t;
+    let mut previous: libc::sigaction = unsafe { std::mem::zeroed() };
+    let ret = unsafe {
+        libc::sigemptyset(&mut action.sa_mask);
+        libc::sigaction(libc::SIGALRM, &action, &mut previous)
+    };
+    assert_eq!(
+        ret,
+        0,
+        "安装 SIGALRM 处理函数失败: {}",
+        std::io::Error::last_os_error()
+    );
+    let _guard = AlarmGuard(previous);
+    unsafe { libc::alarm(secs) };
+    f()
+}
+
+/// `waitpid` 写出的原始状态值，可用 `wifexited!` 等宏解析。
+pub type WaitStatus = libc::c_int;
+
+/// fork 出 `n` 个子进程，第 `i` 个子进程执行 `child_body(i)` 后以 `_exit(0)` 退出
+/// （`child_body` 自行退出时以其退出码为准；panic 时以 101 退出，不会回到测试框架）。
+/// 返回按创建顺序排列的子进程 PID，父进程需自行回收，例如调用 [`reap_all`]。
+pub fn spawn_children<F: Fn(usize) + Copy>(n: usize, child_body: F) -> Vec<libc::pid_t> {
+    let mut pids = Vec::with_capacity(n);
+    for i in 0..n {
+        let pid = unsafe { libc::fork() };
+        if pid == 0 {
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| child_body(i)));
+            unsafe { libc::_exit(if result.is_ok() { 0 } else { 101 }) };
+        }
+        assert!(
+            pid > 0,
+            "fork 第 {i} 个子进程失败: {}",
+            std::io::Error::last_os_error()
+        );
+        pids.push(pid);
+    }
+    pids
+}
+
+/// [`fork_session`] 的子进程 `setsid()` 失败时的退出码。
+pub const SETSID_FAILED_EXIT: libc::c_int = 125;
+
+/// fork 一个子进程，子进程先调用 `setsid()` 成为新会话和新进程组的首进程（没有控制终端），
+/// 再执行 `child`，之后以 `_exit(0)` 退出（`child` 自行退出时以其退出码为准；panic 时以 101 退出；
+/// `setsid` 失败时以 [`SETSID_FAILED_EXIT`] 退出）。返回子进程 PID，父进程需自行回收。
+///
+/// 真实的作业控制测试需要这样的子进程：直接 fork 出的子进程与测试进程同属一个会话和进程组，
+/// 终端停止信号的效果取决于测试框架怎样被启动。`waitpid_linux_abi` 中的 SIGTSTP 用例只覆盖了这种情况。
+/// 注意新会话的首进程组是孤儿进程组，内核会丢弃发给它的 SIGTSTP/SIGTTIN/SIGTTOU（SIGSTOP 不受影响）；
+/// 要观察这些信号使进程停止，应由会话首进程充当 shell：在 `child` 中再 fork 出孙进程，
+/// 调用 `setpgid(0, 0)` 放入新的进程组后再发信号。
+pub fn fork_session<F: FnOnce()>(child: F) -> Result<libc::pid_t> {
+    let pid = unsafe { libc::fork() };
+    if pid == 0 {
+        if unsafe { libc::setsid() } < 0 {
+            unsafe { libc::_exit(SETSID_FAILED_EXIT) };
+        }
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(child));
+        unsafe { libc::_exit(if result.is_ok() { 0 } else { 101 }) };
+    }
+    if pid < 0 {
+        return Err(anyhow!("fork 失败: {}", std::io::Error::last_os_error()));
+    }
+    Ok(pid)
+}
+
+/// 依次阻塞等待 `pids` 中的每个子进程，返回与之同序的 `(pid, 状态)`。
+/// 任一 `waitpid` 失败（`EINTR` 除外）或返回了其他 PID 时报错。
+pub fn reap_all(pids: &[libc::pid_t]) -> Result<Vec<(libc::pid_t, WaitStatus)>> {
+    pids.iter()
+        .map(|&pid| {
+            let (ret, status) = wait_for_eintr_safe(pid, 0)?;
+            if ret != pid {
+                return Err(anyhow!("waitpid({pid}) 返回了 {ret}"));
+            }
+            Ok((pid, status))
+        })
+        .collect()
+}
+
+/// 以 `waitpid(-1, WNOHANG)` 回收当前所有已退出、尚未回收的子进程，直到返回 0（其余子进程仍在运行）
+/// 或 `ECHILD`（已无子进程），返回按回收顺序排列的 `(pid, 状态)`。
+/// 适合放在信号类测试的清理步骤中，避免残留僵尸进程；测试可据返回值断言没有回收到意外的子进程。
+/// 仍在运行的子进程不会被等待，需要时先杀掉再调用。
+pub fn reap_remaining() -> Vec<(libc::pid_t, WaitStatus)> {
+    let mut reaped = Vec::new();
+    loop {
+        let mut status: WaitStatus = 0;
+        let ret = unsafe { libc::waitpid(-1, &mut status, libc::WNOHANG) };
+        if ret > 0 {
+            reaped.push((ret, status));
+            continue;
+        }
+        if ret < 0 && std::io::Error::last_os_error().raw_os_error() == Some(libc::EINTR) {
+            continue;
+        }
+        return reaped;
+    }
+}
+
+/// [`wait_for_state_change`] 两次 `waitpid` 之间的间隔。
+const STATE_CHANGE_POLL: Duration = Duration::from_millis(5);
+
+/// 以 `options | WNOHANG` 轮询 `waitpid(pid, ...)`，直到有子进程状态变化或超过 `timeout`。
+/// 状态变化时返回 `Some((waitpid 返回值, 状态))`，超时返回 `Ok(None)`；`waitpid` 出错（`EINTR` 除外）时报错。
+/// 用于等待停止、继续等异步事件，取代手写的 `for _ in 0..10 { ...; usleep }` 轮询。
+pub fn wait_for_state_change(
+    pid: libc::pid_t,
+    options: libc::c_int,
+    timeout: Duration,
+) -> Result<Option<(libc::pid_t, WaitStatus)>> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        let (ret, status) = wait_for_eintr_safe(pid, options | libc::WNOHANG)?;
+        if ret != 0 {
+            return Ok(Some((ret, status)));
+        }
+        if Instant::now() >= deadline {
+            return Ok(None);
+        }
+        std::thread::sleep(STATE_CHANGE_POLL);
+    }
+}
+
+/// 以 `WEXITED | WNOWAIT` 查看子进程 `pid` 的终止状态而不回收它，
+/// 之后仍可用普通的 `waitpid` 再次取得同一状态。
+/// 返回按 `waitpid` 格式编码的原始状态值；内核不支持 `WNOWAIT`（返回 `EINVAL`）时返回 `Ok(None)`，
+/// 调用方可据此跳过测试。`waitpid` 本身在 Linux 上不接受 `WNOWAIT`，因此这里使用 `waitid`。
+pub fn peek_status(pid: libc::pid_t) -> Result<Option<libc::c_int>> {
+    let mut info: libc::siginfo_t = unsafe { std::mem::zeroed() };
+    loop {
+        let ret = unsafe {
+            libc::waitid(
+                libc::P_PID,
+                pid as libc::id_t,
+                &mut info,
+                libc::WEXITED | libc::WNOWAIT,
+            )
+        };
+        if ret == 0 {
+            break;
+        }
+        let err = std::io::Error::last_os_error();
+        match err.raw_os_error() {
+            Some(libc::EINTR) => continue,
+            Some(libc::EINVAL) => return Ok(None),
+            _ => return Err(anyhow!("waitid({pid}, WEXITED|WNOWAIT) 失败: {err}")),
+        }
+    }
+    let status = unsafe { info.si_status() };
+    match info.si_code {
+        libc::CLD_EXITED => Ok(Some(make_exited(status as u8))),
+        libc::CLD_KILLED => Ok(Some(make_signaled(status, false))),
+        libc::CLD_DUMPED => Ok(Some(make_signaled(status, true))),
+        code => Err(anyhow!("waitid({pid}) 返回了意外的 si_code {code}")),
+    }
+}
+
+/// 断言子进程 `pid` 仍在运行：`kill(pid, 0)` 必须成功，且没有待回收的退出或停止状态
+/// （通过 `waitid(WNOHANG | WNOWAIT)` 查看，不会消费该状态）。
+/// 用于在发送信号前显式确认子进程存活，替代 `usleep` 式的时间猜测。
+#[track_caller]
+pub fn assert_alive(pid: libc::pid_t) {
+    let ret = unsafe { libc::kill(pid, 0) };
+    assert_eq!(
+        ret,
+        0,
+        "子进程 {pid} 应仍存活，但 kill(pid, 0) 失败: {}",
+        std::io::Error::last_os_error()
+    );
+    let mut info: libc::siginfo_t = unsafe { std::mem::zeroed() };
+    let options = libc::WEXITED | libc::WSTOPPED | libc::WNOHANG | libc::WNOWAIT;
+    let ret = unsafe { libc::waitid(libc::P_PID, pid as libc::id_t, &mut info, options) };
+    // 不支持 WNOWAIT 的内核只能依赖 kill(pid, 0) 的结果
+    if ret == 0 {
+        let reported = unsafe { info.si_pid() };
+        assert_eq!(
+            reported, 0,
+            "子进程 {pid} 应仍在运行，但已有待回收的状态 (si_code={})",
+            info.si_code
+        );
+    }
+}
+
+/// 断言子进程 `pid` 已被回收：`waitpid(pid, WNOHANG)` 必须以 `ECHILD` 失败，
+/// 且 `kill(pid, 0)` 以 `ESRCH` 失败（PID 已被复用时后者可能成功，此时只检查前者）。
+#[track_caller]
+pub fn assert_reaped(pid: libc::pid_t) {
+    let mut status: libc::c_int = 0;
+    let ret = unsafe { libc::waitpid(pid, &mut status, libc::WNOHANG) };
+    let err = std::io::Error::last_os_error();
+    assert!(
+        ret == -1 && err.raw_os_error() == Some(libc::ECHILD),
+        "子进程 {pid} 应已被回收，但 waitpid 返回 {ret} ({err})"
+    );
+    let ret = unsafe { libc::kill(pid, 0) };
+    let err = std::io::Error::last_os_error();
+    assert!(
+        ret == 0 || err.raw_os_error() == Some(libc::ESRCH),
+        "子进程 {pid} 已被回收，kill(pid, 0) 应返回 ESRCH，实际为 {err}"
+    );
+}
+
+/// 断言回收之后再次阻塞等待 `pid`（`-1` 表示任意子进程）立即以 `ECHILD` 失败，
+/// 即没有剩下可等待的子进程。用于“等待一次，再等待一次预期 ECHILD”的收尾检查；
+/// 与 [`assert_reaped`] 不同，这里不带 `WNOHANG`，检查的正是阻塞的 `waitpid` 路径。
+#[track_caller]
+pub fn assert_no_more_children(pid: libc::pid_t) {
+    let mut status: libc::c_int = 0;
+    let ret = unsafe { libc::waitpid(pid, &mut status, 0) };
+    let err = std::io::Error::last_os_error();
+    assert!(
+        ret == -1 && err.raw_os_error() == Some(libc::ECHILD),
+        "回收后再次 waitpid({pid}) 应以 ECHILD 失败，实际返回 {ret}（{}）",
+        if ret == -1 {
+            err.to_string()
+        } else {
+            describe_status(status)
+        }
+    );
+}
+
+/// 构造“正常退出、退出码为 `code`”的原始 `waitpid` 状态值，
+/// 用于在不 fork 的情况下测试状态解析逻辑。
+pub const fn make_exited(code: u8) -> libc::c_int {
+    (code as libc::c_int) << 8
+}
+
+/// 构造“被信号 `sig` 终止”的原始状态值，`core` 为 true 时带上 core dump 标记。
+pub const fn make_signaled(sig: libc::c_int, core: bool) -> libc::c_int {
+    (sig & 0x7f) | if core { 0x80 } else { 0 }
+}
+
+/// 构造“被信号 `sig` 停止”的原始状态值（`WUNTRACED` 场景）。
+pub const fn make_stopped(sig: libc::c_int) -> libc::c_int {
+    ((sig & 0xff) << 8) | 0x7f
+}
+
+/// 构造“从停止状态恢复”的原始状态值（`WCONTINUED` 场景）。
+pub const fn make_continued() -> libc::c_int {
+    0xffff
+}
+
+/// 信号编号对应的名字，例如 `SIGKILL`；未知编号返回 `signal N`。
+pub fn signal_name(sig: libc::c_int) -> String {
+    let name = match sig {
+        libc::SIGHUP => "SIGHUP",
+        libc::SIGINT => "SIGINT",
+        libc::SIGQUIT => "SIGQUIT",
+        libc::SIGILL => "SIGILL",
+        libc::SIGTRAP => "SIGTRAP",
+        libc::SIGABRT => "SIGABRT",
+        libc::SIGBUS => "SIGBUS",
+        libc::SIGFPE => "SIGFPE",
+        libc::SIGKILL => "SIGKILL",
+        libc::SIGUSR1 => "SIGUSR1",
+        libc::SIGSEGV => "SIGSEGV",
+        libc::SIGUSR2 => "SIGUSR2",
+        libc::SIGPIPE => "SIGPIPE",
+        libc::SIGALRM => "SIGALRM",
+        libc::SIGTERM => "SIGTERM",
+        libc::SIGCHLD => "SIGCHLD",
+        libc::SIGCONT => "SIGCONT",
+        libc::SIGSTOP => "SIGSTOP",
+        libc::SIGTSTP => "SIGTSTP",
+        libc::SIGTTIN => "SIGTTIN",
+        libc::SIGTTOU => "SIGTTOU",
+        libc::SIGURG => "SIGURG",
+        libc::SIGXCPU => "SIGXCPU",
+        libc::SIGXFSZ => "SIGXFSZ",
+        libc::SIGVTALRM => "SIGVTALRM",
+        libc::SIGPROF => "SIGPROF",
+        libc::SIGWINCH => "SIGWINCH",
+        libc::SIGIO => "SIGIO",
+        libc::SIGSYS => "SIGSYS",
+        _ => return format!("signal {sig}"),
+    };
+    name.to_string()
+}
+
+/// 把原始 `waitpid` 状态值描述成一句可读的话，用于断言失败信息。
+pub fn describe_status(status: libc::c_int) -> String {
+    if wifexited!(status) {
+        format!("正常退出，退出码 {}", wexitstatus!(status))
+    } else if wifsignaled!(status) {
+        let sig = wtermsig!(status);
+        let core = if wcoredump!(status) {
+            "，生成了 core dump"
+        } else {
+            ""
+        };
+        format!("被 {} ({sig}) 终止{core}", signal_name(sig))
+    } else if wifstopped!(status) {
+        let sig = wstopsig!(status);
+        format!("被 {} ({sig}) 停止", signal_name(sig))
+    } else if wifcontinued!(status) {
+        "从停止状态恢复".to_string()
+    } else {
+        "未知状态".to_string()
+    }
+}
+
+/// 断言 `status` 表示子进程正常退出且退出码为 `expected_code`，
+/// 否则 panic 并说明实际状态（例如被哪个信号终止）。
+#[track_caller]
+pub fn assert_exited(status: libc::c_int, expected_code: libc::c_int) {
+    assert!(
+        wifexited!(status) && wexitstatus!(status) == expected_code,
+        "子进程应正常退出，退出码 {expected_code}；实际{} (status=0x{status:x})",
+        describe_status(status)
+    );
+}
+
+/// 断言 `status` 表示子进程被信号 `expected_sig` 终止，
+/// 否则 panic 并说明实际状态，信号以名字给出。
+#[track_caller]
+pub fn assert_signaled(status: libc::c_int, expected_sig: libc::c_int) {
+    assert!(
+        wifsignaled!(status) && wtermsig!(status) == expected_sig,
+        "子进程应被 {} ({expected_sig}) 终止；实际{} (status=0x{status:x})",
+        signal_name(expected_sig),
+        describe_status(status)
+    );
+}
+
 /// 子进程执行结果，包含退出状态以及标准输出/错误（UTF-8）。
 #[derive(Debug)]
 pub struct CommandOutput {
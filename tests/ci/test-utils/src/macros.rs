@@ -66,3 +66,110 @@ macro_rules! wcoredump {
         ($status & 0x80) != 0
     };
 }
+
+#[cfg(all(test, unix))]
+mod tests {
+    use crate::{
+        assert_exited, assert_signaled, make_continued, make_exited, make_signaled, make_stopped,
+    };
+
+    /// 覆盖全部 16 位状态值，外加高位被置位的情况：Linux 只用低 16 位编码状态。
+    fn sweep() -> impl Iterator<Item = libc::c_int> {
+        (0..=0xffff).chain([0x1_0000, 0x7fff_ff7f, -1, libc::c_int::MIN])
+    }
+
+    #[test]
+    fn wait_macros_match_libc() {
+        for status in sweep() {
+            assert_eq!(
+                wifexited!(status),
+                libc::WIFEXITED(status),
+                "WIFEXITED({status:#x})"
+            );
+            assert_eq!(
+                wexitstatus!(status),
+                libc::WEXITSTATUS(status),
+                "WEXITSTATUS({status:#x})"
+            );
+            assert_eq!(
+                wifsignaled!(status),
+                libc::WIFSIGNALED(status),
+                "WIFSIGNALED({status:#x})"
+            );
+            assert_eq!(
+                wtermsig!(status),
+                libc::WTERMSIG(status),
+                "WTERMSIG({status:#x})"
+            );
+            assert_eq!(
+                wifstopped!(status),
+                libc::WIFSTOPPED(status),
+                "WIFSTOPPED({status:#x})"
+            );
+            assert_eq!(
+                wstopsig!(status),
+                libc::WSTOPSIG(status),
+                "WSTOPSIG({status:#x})"
+            );
+            assert_eq!(
+                wifcontinued!(status),
+                libc::WIFCONTINUED(status),
+                "WIFCONTINUED({status:#x})"
+            );
+            assert_eq!(
+                wcoredump!(status),
+                libc::WCOREDUMP(status),
+                "WCOREDUMP({status:#x})"
+            );
+        }
+    }
+
+    /// 记录预期的编码：退出码在 8..16 位；信号在低 7 位，0x80 表示 core dump；
+    /// 停止为 `(sig << 8) | 0x7f`；继续为 `0xffff`。
+    #[test]
+    fn wait_macros_decode_documented_encoding() {
+        let exited = make_exited(42);
+        assert!(wifexited!(exited) && !wifsignaled!(exited) && !wifstopped!(exited));
+        assert_eq!(wexitstatus!(exited), 42);
+
+        let signaled = make_signaled(libc::SIGSEGV, true);
+        assert!(wifsignaled!(signaled) && !wifexited!(signaled) && !wifstopped!(signaled));
+        assert_eq!(wtermsig!(signaled), libc::SIGSEGV);
+        assert!(wcoredump!(signaled));
+        assert!(!wcoredump!(make_signaled(libc::SIGTERM, false)));
+
+        let stopped = make_stopped(libc::SIGSTOP);
+        assert!(wifstopped!(stopped) && !wifsignaled!(stopped) && !wifexited!(stopped));
+        assert_eq!(wstopsig!(stopped), libc::SIGSTOP);
+
+        let continued = make_continued();
+        assert!(wifcontinued!(continued) && !wifexited!(continued) && !wifsignaled!(continued));
+    }
+
+    #[test]
+    fn status_assertions_name_the_actual_outcome() {
+        assert_exited(make_exited(3), 3);
+        assert_signaled(make_signaled(libc::SIGKILL, false), libc::SIGKILL);
+
+        let message =
+            std::panic::catch_unwind(|| assert_exited(make_signaled(libc::SIGSEGV, true), 0))
+                .expect_err("被信号终止不应通过 assert_exited");
+        let message = message
+            .downcast_ref::<String>()
+            .expect("panic 信息应为字符串");
+        assert!(
+            message.contains("被 SIGSEGV (11) 终止，生成了 core dump"),
+            "{message}"
+        );
+
+        let message = std::panic::catch_unwind(|| assert_signaled(make_exited(1), libc::SIGTERM))
+            .expect_err("正常退出不应通过 assert_signaled");
+        let message = message
+            .downcast_ref::<String>()
+            .expect("panic 信息应为字符串");
+        assert!(
+            message.contains("应被 SIGTERM (15) 终止") && message.contains("退出码 1"),
+            "{message}"
+        );
+    }
+}